@@ -0,0 +1,263 @@
+use crate::algorithms::minimax_basic::{
+    minimax_clone, minimax_clone_with, minimax_mut, minimax_mut_with,
+};
+use crate::algorithms::minimax_cached::{minimax_cached, minimax_cached_with};
+use crate::algorithms::minimax_counting::{minimax_counting, minimax_counting_with};
+use crate::algorithms::minimax_symm::{minimax_symm, minimax_symm_with};
+use crate::algorithms::pruning::{ForcedMoves, PruningRule};
+use crate::basic::*;
+use crate::board::bit_board::BitBoard;
+use crate::board::{Board, CloneBoard, MutBoard};
+use crate::random::{Game, Rng, random_position};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::ops::RangeInclusive;
+
+/// A solver under test, taking the board, depth and player to move.
+pub type Solver<B = BitBoard> = fn(B, usize, Token) -> Option<Token>;
+
+/// Every minimax solver in the crate.
+pub const SOLVERS: [(&str, Solver); 5] = solvers();
+
+/// Every minimax solver in the crate, extending the search through forced moves.
+/// With extensions, a position can be reached with different depths left.
+pub const FORCED_MOVE_SOLVERS: [(&str, Solver); 5] = solvers_with::<_, _, _, _, ForcedMoves>();
+
+/// Every minimax solver in the crate, on boards of any size.
+pub const fn solvers<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + MutBoard<W, H, K> + Hash,
+>() -> [(&'static str, Solver<B>); 5] {
+    [
+        ("minimax_mut", |mut board, depth, curr| {
+            minimax_mut(&mut board, depth, curr)
+        }),
+        ("minimax_clone", minimax_clone),
+        ("minimax_cached", minimax_cached),
+        ("minimax_symm", minimax_symm),
+        ("minimax_counting", minimax_counting),
+    ]
+}
+
+/// Every minimax solver in the crate, on boards of any size,
+/// cutting off any position decided by the pruning rule `R`.
+pub const fn solvers_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + MutBoard<W, H, K> + Hash,
+    R: PruningRule + Default,
+>() -> [(&'static str, Solver<B>); 5] {
+    [
+        ("minimax_mut_with", |mut board, depth, curr| {
+            minimax_mut_with(&mut board, depth, curr, &R::default())
+        }),
+        ("minimax_clone_with", |board, depth, curr| {
+            minimax_clone_with(board, depth, curr, &R::default())
+        }),
+        ("minimax_cached_with", |board, depth, curr| {
+            minimax_cached_with(board, depth, curr, &R::default())
+        }),
+        ("minimax_symm_with", |board, depth, curr| {
+            minimax_symm_with(board, depth, curr, &R::default())
+        }),
+        ("minimax_counting_with", |board, depth, curr| {
+            minimax_counting_with(board, depth, curr, &R::default())
+        }),
+    ]
+}
+
+/// A position and depth the solvers disagree on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The columns played from the empty board to reach the position.
+    pub moves: Vec<column::Idx>,
+    pub depth: usize,
+    /// The result of each solver.
+    pub results: Vec<(&'static str, Option<Token>)>,
+}
+
+impl Mismatch {
+    pub fn board(&self) -> BitBoard {
+        play(&self.moves).expect("the moves make a valid position")
+    }
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let moves: Vec<String> = self.moves.iter().map(|col| col.to_string()).collect();
+        write!(f, "{}", self.board().to_string())?;
+        writeln!(
+            f,
+            "solvers disagree at depth {} after moves [{}]",
+            self.depth,
+            moves.join(", ")
+        )?;
+        for (name, result) in &self.results {
+            writeln!(f, "    {name}: {result:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves `positions` random mid-game positions, each reached with a number of plies
+/// in the given range, at each of the given depths, checking that the solvers agree.
+/// Returns the first mismatch found, shrunk to the fewest moves and smallest depth.
+pub fn cross_check(
+    solvers: &[(&'static str, Solver)],
+    seed: u64,
+    positions: usize,
+    plies: RangeInclusive<usize>,
+    depths: &[usize],
+) -> Result<(), Mismatch> {
+    let mut rng = Rng::seeded(seed);
+    for _ in 0..positions {
+        let count = plies.start() + rng.below(plies.end() - plies.start() + 1);
+        let game: Game<BitBoard> =
+            random_position(&mut rng, count).expect("random games often last that long");
+        let moves = game.moves;
+        for &depth in depths {
+            if let Some(mismatch) = check(solvers, &moves, depth) {
+                return Err(shrink(solvers, mismatch));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Runs every solver on the position, returning a mismatch if they disagree.
+fn check(
+    solvers: &[(&'static str, Solver)],
+    moves: &[column::Idx],
+    depth: usize,
+) -> Option<Mismatch> {
+    let board = play(moves)?;
+    let curr = board.curr_player();
+    let results: Vec<(&'static str, Option<Token>)> = solvers
+        .iter()
+        .map(|(name, solver)| (*name, solver(board.clone(), depth, curr)))
+        .collect();
+
+    if results.windows(2).all(|pair| pair[0].1 == pair[1].1) {
+        None
+    } else {
+        Some(Mismatch {
+            moves: moves.to_vec(),
+            depth,
+            results,
+        })
+    }
+}
+
+/// Shrinks a mismatch by removing pairs of moves and single moves, then lowering the depth,
+/// while it still fails. Removing a pair keeps the colour of every later token.
+fn shrink(solvers: &[(&'static str, Solver)], mut mismatch: Mismatch) -> Mismatch {
+    for size in [2, 1] {
+        let mut i = 0;
+        while i + size <= mismatch.moves.len() {
+            let mut fewer = mismatch.moves.clone();
+            fewer.drain(i..i + size);
+            match check(solvers, &fewer, mismatch.depth) {
+                Some(smaller) => mismatch = smaller,
+                None => i += 1,
+            }
+        }
+    }
+
+    while let Some(shallower) = mismatch
+        .depth
+        .checked_sub(1)
+        .and_then(|depth| check(solvers, &mismatch.moves, depth))
+    {
+        mismatch = shallower;
+    }
+    mismatch
+}
+
+/// Plays the moves from the empty board, returning None if a move is
+/// into a full column or ends the game.
+fn play(moves: &[column::Idx]) -> Option<BitBoard> {
+    let mut board = BitBoard::EMPTY;
+    let mut curr = Token::START;
+    for col in moves {
+        let cell = board.place(col, &curr)?;
+        if board.won_at(&cell) {
+            return None;
+        }
+        curr = curr.next();
+    }
+    Some(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solvers_agree() {
+        if let Err(mismatch) = cross_check(&SOLVERS, 0, 200, 8..=24, &[2, 4, 6]) {
+            panic!("{mismatch}");
+        }
+    }
+
+    #[test]
+    fn uncached_forced_move_solvers_agree() {
+        if let Err(mismatch) = cross_check(&FORCED_MOVE_SOLVERS[..2], 0, 50, 8..=24, &[2, 4]) {
+            panic!("{mismatch}");
+        }
+    }
+
+    #[test]
+    fn forced_move_solvers_agree() {
+        if let Err(mismatch) = cross_check(&FORCED_MOVE_SOLVERS, 0, 100, 8..=24, &[3, 4]) {
+            panic!("{mismatch}");
+        }
+    }
+
+    #[test]
+    fn full_board_is_a_draw() {
+        // Red fills the board without making a line, with depth to spare
+        let board = BitBoard::read(
+            "|.RYYRYY|
+             |YRYRRRY|
+             |YRYYRRR|
+             |RYRYYYR|
+             |RRRYYRY|
+             |YYYRRRY|",
+        );
+        for (name, solver) in SOLVERS.iter().chain(&FORCED_MOVE_SOLVERS) {
+            for depth in 1..=3 {
+                assert_eq!(
+                    solver(board.clone(), depth, Token::Red),
+                    None,
+                    "{name} at depth {depth}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn mismatch_is_shrunk() {
+        // disagrees with `minimax_clone` whenever Red has a token in the first column
+        let suspicious: Solver = |board, depth, curr| {
+            if (0..row::COUNT).any(|row| {
+                board.get(&Cell {
+                    col: column::Idx::ZERO,
+                    row: row::Idx::raw(row),
+                }) == Some(Token::Red)
+            }) {
+                Some(Token::Red)
+            } else {
+                minimax_clone(board, depth, curr)
+            }
+        };
+        let solvers = [SOLVERS[1], ("suspicious", suspicious)];
+        let mismatch = cross_check(&solvers, 0, 100, 8..=24, &[3]).unwrap_err();
+
+        assert_eq!(mismatch.moves.len(), 2);
+        assert_eq!(mismatch.moves[1], column::Idx::ZERO);
+        assert_eq!(mismatch.depth, 0);
+    }
+}
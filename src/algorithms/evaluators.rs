@@ -0,0 +1,235 @@
+use crate::basic::*;
+use crate::board::Board;
+use crate::lines;
+
+/// Trait for heuristic scoring of non-terminal positions,
+/// used by depth-limited searches once they reach their horizon.
+pub trait Evaluator {
+    /// Scores the board from the view of `player`: positive is good for `player`,
+    /// negative is good for the opponent.
+    /// Scores should stay well below `negamax::WIN_SCORE` in magnitude.
+    fn evaluate<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        player: Token,
+    ) -> isize;
+}
+
+/// Returns the empty cells that would complete a line for `player`, or whatever else
+/// the board is won with, whether or not they can be played immediately.
+pub fn threats<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    player: Token,
+) -> Vec<Cell<W, H>> {
+    let own = lines::token_mask(board, player);
+
+    let mut threats = 0;
+    for mask in lines::open_lines(board, player) {
+        if (mask & own).count_ones() + 1 == mask.count_ones() {
+            threats |= mask & !own;
+        }
+    }
+
+    (0..lines::Lines::<W, H, K>::CELL_COUNT)
+        .filter(|i| threats >> i & 1 == 1)
+        .map(lines::index_cell)
+        .collect()
+}
+
+/// Turns a score under the usual rules into one under the board's rules. Under misère
+/// rules a player's lines and threats are squares they must avoid filling, so count against them.
+fn under_rules<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    score: isize,
+) -> isize {
+    if B::MISERE { -score } else { score }
+}
+
+/// Returns true if a threat at the given row favours `player` in the endgame:
+/// the first player wants threats on odd rows, the second on even rows
+/// (counting the bottom row as row 1).
+pub fn favourable_row<const H: usize>(player: Token, row: row::Idx<H>) -> bool {
+    let odd = usize::from(row) % 2 == 0;
+    if player == Token::START { odd } else { !odd }
+}
+
+/// Scores windows of `K` that are still open (contain tokens of only one colour),
+/// weighted by how many tokens they already hold, or any other ways to win the board has.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpenLines;
+
+impl OpenLines {
+    /// Weight of an open window of `n` cells holding `n`, `n - 1`, `n - 2` or `n - 3` tokens.
+    /// Windows holding fewer tokens count 1, and empty ones nothing.
+    const WEIGHTS: [isize; 4] = [256, 16, 4, 1];
+
+    /// Sums the weights of the lines still open to `player`.
+    fn score<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        board: &B,
+        player: Token,
+    ) -> isize {
+        let own = lines::token_mask(board, player);
+        lines::open_lines(board, player)
+            .map(|mask| match (mask & own).count_ones() {
+                0 => 0,
+                count => Self::WEIGHTS
+                    .get((mask.count_ones() - count) as usize)
+                    .copied()
+                    .unwrap_or(1),
+            })
+            .sum()
+    }
+}
+
+impl Evaluator for OpenLines {
+    fn evaluate<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        player: Token,
+    ) -> isize {
+        under_rules::<W, H, K, B>(Self::score(board, player) - Self::score(board, player.next()))
+    }
+}
+
+/// Scores the difference in the number of threats,
+/// empty cells that would complete a line.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreatCount;
+
+impl Evaluator for ThreatCount {
+    fn evaluate<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        player: Token,
+    ) -> isize {
+        under_rules::<W, H, K, B>(
+            threats(board, player).len() as isize - threats(board, player.next()).len() as isize,
+        )
+    }
+}
+
+/// Scores threats by their row parity, as odd/even threats decide who wins
+/// once the board fills up. Threats on a favourable row count triple.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreatParity;
+
+impl ThreatParity {
+    fn score<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        board: &B,
+        player: Token,
+    ) -> isize {
+        threats(board, player)
+            .iter()
+            .map(|cell| {
+                if favourable_row(player, cell.row) {
+                    3
+                } else {
+                    1
+                }
+            })
+            .sum()
+    }
+}
+
+impl Evaluator for ThreatParity {
+    fn evaluate<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        player: Token,
+    ) -> isize {
+        under_rules::<W, H, K, B>(Self::score(board, player) - Self::score(board, player.next()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{bit_board::BitBoard, misere::Misere};
+
+    #[test]
+    fn empty_is_even() {
+        let board: BitBoard = BitBoard::EMPTY;
+        assert_eq!(OpenLines.evaluate(&board, Token::Yellow), 0);
+        assert_eq!(ThreatCount.evaluate(&board, Token::Yellow), 0);
+        assert_eq!(ThreatParity.evaluate(&board, Token::Yellow), 0);
+    }
+
+    #[test]
+    fn centre_beats_edge() {
+        let centre: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |.......|
+             |...Y...|",
+        );
+        let edge: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |.......|
+             |Y......|",
+        );
+        assert!(
+            OpenLines.evaluate(&centre, Token::Yellow) > OpenLines.evaluate(&edge, Token::Yellow)
+        );
+        assert_eq!(
+            OpenLines.evaluate(&centre, Token::Yellow),
+            -OpenLines.evaluate(&centre, Token::Red)
+        );
+    }
+
+    #[test]
+    fn dead_position_is_even() {
+        let board: BitBoard = BitBoard::read(
+            "|RR.YRR.|
+             |YYRRYYR|
+             |RRYYRRY|
+             |YYRRYYR|
+             |YYRRYYR|
+             |YYRRYYR|",
+        );
+        assert_eq!(OpenLines.evaluate(&board, Token::Yellow), 0);
+        assert_eq!(OpenLines.evaluate(&board, Token::Red), 0);
+    }
+
+    #[test]
+    fn threats_found() {
+        let board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |R.R....|
+             |YYY....|",
+        );
+        assert_eq!(
+            threats(&board, Token::Yellow),
+            vec![Cell {
+                col: column::Idx::raw(3),
+                row: row::Idx::raw(0)
+            }]
+        );
+        assert_eq!(ThreatCount.evaluate(&board, Token::Yellow), 1);
+        assert_eq!(ThreatParity.evaluate(&board, Token::Yellow), 3);
+    }
+
+    #[test]
+    fn misere_counts_threats_against() {
+        let board: Misere<BitBoard> = Misere(BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |R.R....|
+             |YYY....|",
+        ));
+        assert_eq!(ThreatCount.evaluate(&board, Token::Yellow), -1);
+        assert_eq!(ThreatParity.evaluate(&board, Token::Yellow), -3);
+        assert_eq!(
+            OpenLines.evaluate(&board, Token::Yellow),
+            -OpenLines.evaluate(&board.0, Token::Yellow)
+        );
+    }
+}
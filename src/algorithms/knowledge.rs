@@ -0,0 +1,688 @@
+use crate::basic::*;
+use crate::board::Board;
+use crate::lines;
+use std::fmt::Display;
+
+/// Allis's strategic rules, used by the controller of zugzwang (here, the second player)
+/// to refute the groups the first player could still complete.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    Claimeven,
+    Baseinverse,
+    Vertical,
+    Aftereven,
+    Lowinverse,
+    Highinverse,
+    Baseclaim,
+    Before,
+    Specialbefore,
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// A single application of a rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solution {
+    pub rule: Rule,
+    /// Bitmask of the squares the rule relies on, laid out as in `lines`.
+    pub squares: u128,
+    /// Bitmask of the columns the rule relies on.
+    pub columns: u8,
+    /// Bitmask over `lines::LINES` of the groups the rule refutes.
+    pub solves: u128,
+}
+
+impl Solution {
+    /// Returns true if the two solutions can be used together.
+    fn compatible(&self, other: &Solution) -> bool {
+        if self.squares & other.squares != 0 {
+            return false;
+        }
+        // inverses rely on the parity of whole columns
+        let inverse = |rule| matches!(rule, Rule::Lowinverse | Rule::Highinverse);
+        if (inverse(self.rule) || inverse(other.rule)) && self.columns & other.columns != 0 {
+            return false;
+        }
+        true
+    }
+
+    /// Returns the cells of the squares the rule relies on.
+    pub fn cells(&self) -> Vec<Cell> {
+        (0..lines::CELL_COUNT)
+            .filter(|i| self.squares >> i & 1 == 1)
+            .map(lines::index_cell)
+            .collect()
+    }
+}
+
+/// A proof that the second player can at least draw: a set of compatible solutions
+/// that together refute every group the first player could still complete.
+#[derive(Clone, Debug)]
+pub struct Proof {
+    pub solutions: Vec<Solution>,
+    /// Bitmask over `lines::LINES` of the groups that needed refuting.
+    pub groups: u128,
+}
+
+impl Proof {
+    /// For every group the first player could still complete,
+    /// returns the group and the solution that refutes it.
+    pub fn coverage(&self) -> Vec<(lines::Line, &Solution)> {
+        (0..lines::COUNT)
+            .filter(|i| self.groups >> i & 1 == 1)
+            .map(|i| {
+                let solution = self
+                    .solutions
+                    .iter()
+                    .find(|solution| solution.solves >> i & 1 == 1)
+                    .unwrap();
+                (lines::LINES[i], solution)
+            })
+            .collect()
+    }
+
+    /// A readable listing of which rule refutes which group.
+    pub fn explain(&self) -> String {
+        let cells = |cells: &[Cell]| {
+            cells
+                .iter()
+                .map(|cell| format!("({}, {})", cell.col, cell.row))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let mut string = String::new();
+        for (group, solution) in self.coverage() {
+            string.push_str(&format!(
+                "{} refuted by {} on {}\n",
+                cells(&group),
+                solution.rule,
+                cells(&solution.cells())
+            ));
+        }
+        string
+    }
+}
+
+/// The position reduced to the bitmasks the rules work on.
+struct Position {
+    yellow: u128,
+    red: u128,
+    heights: [usize; column::COUNT],
+    /// Groups the first player could still complete.
+    groups: u128,
+}
+
+impl Position {
+    fn new<B: Board>(board: &B) -> Self {
+        let yellow = lines::token_mask(board, Token::Yellow);
+        let red = lines::token_mask(board, Token::Red);
+        let filled = yellow | red;
+
+        let mut heights = [0; column::COUNT];
+        for (col, height) in heights.iter_mut().enumerate() {
+            *height = (filled >> (col * row::COUNT) & 0b111111).count_ones() as usize;
+        }
+
+        let mut groups = 0;
+        for (i, mask) in lines::LINE_MASKS.iter().enumerate() {
+            if mask & red == 0 {
+                groups |= 1 << i;
+            }
+        }
+
+        Position {
+            yellow,
+            red,
+            heights,
+            groups,
+        }
+    }
+
+    fn square(col: usize, row: usize) -> usize {
+        col * row::COUNT + row
+    }
+
+    fn is_empty(&self, col: usize, row: usize) -> bool {
+        row >= self.heights[col] && row < row::COUNT
+    }
+
+    /// Returns the directly playable square in the column, if it is not full.
+    fn playable(&self, col: usize) -> Option<usize> {
+        (self.heights[col] < row::COUNT).then(|| Self::square(col, self.heights[col]))
+    }
+
+    /// Even squares are on rows 2, 4 and 6 counting from 1.
+    fn is_even(square: usize) -> bool {
+        square % row::COUNT % 2 == 1
+    }
+
+    /// Returns the first player's groups containing every given square.
+    fn groups_with(&self, squares: &[usize]) -> u128 {
+        squares.iter().fold(self.groups, |groups, &sq| {
+            groups & lines::CELL_LINE_MASKS[sq]
+        })
+    }
+
+    fn solution(&self, rule: Rule, squares: &[usize], solves: u128) -> Solution {
+        Solution {
+            rule,
+            squares: squares.iter().fold(0, |mask, sq| mask | 1 << sq),
+            columns: squares
+                .iter()
+                .fold(0, |mask, sq| mask | 1 << (sq / row::COUNT)),
+            solves: solves & self.groups,
+        }
+    }
+
+    /// Claimeven on the empty square `sq` and the square above, if it applies.
+    fn claimeven_above(&self, sq: usize) -> Option<(usize, usize)> {
+        let (col, row) = (sq / row::COUNT, sq % row::COUNT);
+        (!Self::is_even(sq) && self.is_empty(col, row) && row + 1 < row::COUNT)
+            .then_some((sq, sq + 1))
+    }
+
+    /// Vertical on the empty square `sq` and the square above, if it applies.
+    fn vertical_above(&self, sq: usize) -> Option<(usize, usize)> {
+        let (col, row) = (sq / row::COUNT, sq % row::COUNT);
+        (Self::is_even(sq) && self.is_empty(col, row) && row + 1 < row::COUNT)
+            .then_some((sq, sq + 1))
+    }
+
+    fn claimevens(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for col in 0..column::COUNT {
+            for row in (1..row::COUNT).step_by(2) {
+                if self.is_empty(col, row - 1) {
+                    let upper = Self::square(col, row);
+                    out.push(self.solution(
+                        Rule::Claimeven,
+                        &[upper - 1, upper],
+                        self.groups_with(&[upper]),
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    fn baseinverses(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for col_a in 0..column::COUNT {
+            for col_b in col_a + 1..column::COUNT {
+                if let (Some(a), Some(b)) = (self.playable(col_a), self.playable(col_b)) {
+                    out.push(self.solution(Rule::Baseinverse, &[a, b], self.groups_with(&[a, b])));
+                }
+            }
+        }
+        out
+    }
+
+    fn verticals(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for col in 0..column::COUNT {
+            for row in (2..row::COUNT).step_by(2) {
+                if self.is_empty(col, row - 1) {
+                    let upper = Self::square(col, row);
+                    out.push(self.solution(
+                        Rule::Vertical,
+                        &[upper - 1, upper],
+                        self.groups_with(&[upper - 1, upper]),
+                    ));
+                }
+            }
+        }
+        out
+    }
+
+    fn afterevens(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for (i, &mask) in lines::LINE_MASKS.iter().enumerate() {
+            if mask & self.yellow != 0 {
+                continue;
+            }
+            let empties: Vec<usize> = (0..lines::CELL_COUNT)
+                .filter(|&sq| mask >> sq & 1 == 1 && (self.red >> sq & 1 == 0))
+                .collect();
+
+            // the second player must be able to claim every empty square with a claimeven
+            let claimevens: Option<Vec<(usize, usize)>> = empties
+                .iter()
+                .map(|&sq| {
+                    (Self::is_even(sq))
+                        .then(|| self.claimeven_above(sq - 1))
+                        .flatten()
+                })
+                .collect();
+            let Some(claimevens) = claimevens else {
+                continue;
+            };
+            if empties.is_empty() || lines::LINES[i][0].col == lines::LINES[i][3].col {
+                // vertical afterevens are just claimevens
+                continue;
+            }
+
+            let mut squares = Vec::new();
+            let mut solves = 0;
+            for &(lower, upper) in &claimevens {
+                squares.extend([lower, upper]);
+                solves |= self.groups_with(&[upper]);
+            }
+
+            // groups with a square above the aftereven square in every aftereven column
+            let mut above = self.groups;
+            for &sq in &empties {
+                let col = sq / row::COUNT;
+                let mut in_col = 0;
+                for row in sq % row::COUNT + 1..row::COUNT {
+                    in_col |= self.groups_with(&[Self::square(col, row)]);
+                }
+                above &= in_col;
+            }
+            solves |= above;
+
+            out.push(self.solution(Rule::Aftereven, &squares, solves));
+        }
+        out
+    }
+
+    fn lowinverses(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        let pairs: Vec<(usize, usize)> = (0..column::COUNT)
+            .flat_map(|col| {
+                (2..row::COUNT)
+                    .step_by(2)
+                    .filter(move |&row| self.is_empty(col, row - 1))
+                    .map(move |row| (Self::square(col, row - 1), Self::square(col, row)))
+            })
+            .collect();
+
+        for (i, &(lower_a, upper_a)) in pairs.iter().enumerate() {
+            for &(lower_b, upper_b) in &pairs[i + 1..] {
+                if lower_a / row::COUNT == lower_b / row::COUNT {
+                    continue;
+                }
+                let solves = self.groups_with(&[upper_a, upper_b])
+                    | self.groups_with(&[lower_a, upper_a])
+                    | self.groups_with(&[lower_b, upper_b]);
+                out.push(self.solution(
+                    Rule::Lowinverse,
+                    &[lower_a, upper_a, lower_b, upper_b],
+                    solves,
+                ));
+            }
+        }
+        out
+    }
+
+    fn highinverses(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        let triples: Vec<[usize; 3]> = (0..column::COUNT)
+            .flat_map(|col| {
+                (3..row::COUNT)
+                    .step_by(2)
+                    .filter(move |&row| self.is_empty(col, row - 2))
+                    .map(move |row| {
+                        let upper = Self::square(col, row);
+                        [upper - 2, upper - 1, upper]
+                    })
+            })
+            .collect();
+
+        for (i, &[low_a, mid_a, up_a]) in triples.iter().enumerate() {
+            for &[low_b, mid_b, up_b] in &triples[i + 1..] {
+                if low_a / row::COUNT == low_b / row::COUNT {
+                    continue;
+                }
+                let mut solves = self.groups_with(&[up_a, up_b])
+                    | self.groups_with(&[mid_a, mid_b])
+                    | self.groups_with(&[mid_a, up_a])
+                    | self.groups_with(&[mid_b, up_b]);
+                if self.playable(low_a / row::COUNT) == Some(low_a) {
+                    solves |= self.groups_with(&[low_a, up_b]);
+                }
+                if self.playable(low_b / row::COUNT) == Some(low_b) {
+                    solves |= self.groups_with(&[low_b, up_a]);
+                }
+                out.push(self.solution(
+                    Rule::Highinverse,
+                    &[low_a, mid_a, up_a, low_b, mid_b, up_b],
+                    solves,
+                ));
+            }
+        }
+        out
+    }
+
+    fn baseclaims(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        let playable: Vec<usize> = (0..column::COUNT)
+            .filter_map(|col| self.playable(col))
+            .collect();
+
+        for &second in &playable {
+            let above = second + 1;
+            if second % row::COUNT + 1 >= row::COUNT || !Self::is_even(above) {
+                continue;
+            }
+            for &first in &playable {
+                for &third in &playable {
+                    if first == second || third == second || first >= third {
+                        continue;
+                    }
+                    // the first and third squares play symmetric roles, so try both ways
+                    for (a, c) in [(first, third), (third, first)] {
+                        let solves = self.groups_with(&[a, above]) | self.groups_with(&[second, c]);
+                        out.push(self.solution(Rule::Baseclaim, &[a, second, above, c], solves));
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the empty squares of groups without first player tokens,
+    /// with none of them on the top row.
+    fn before_groups(&self) -> Vec<Vec<usize>> {
+        lines::LINE_MASKS
+            .iter()
+            .zip(lines::LINES.iter())
+            .filter(|(mask, line)| *mask & self.yellow == 0 && line[0].col != line[3].col)
+            .map(|(mask, _)| {
+                (0..lines::CELL_COUNT)
+                    .filter(|&sq| mask >> sq & 1 == 1 && self.red >> sq & 1 == 0)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|empties| {
+                !empties.is_empty() && empties.iter().all(|sq| sq % row::COUNT + 1 < row::COUNT)
+            })
+            .collect()
+    }
+
+    /// The claimevens and verticals that let the second player follow up
+    /// on every empty square of a before group.
+    fn before_pairs(&self, empties: &[usize]) -> (Vec<usize>, u128) {
+        let mut squares = Vec::new();
+        let mut solves = 0;
+        for &sq in empties {
+            if let Some((lower, upper)) = self.claimeven_above(sq) {
+                squares.extend([lower, upper]);
+                solves |= self.groups_with(&[upper]);
+            } else if let Some((lower, upper)) = self.vertical_above(sq) {
+                squares.extend([lower, upper]);
+                solves |= self.groups_with(&[lower, upper]);
+            }
+        }
+        (squares, solves)
+    }
+
+    fn befores(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for empties in self.before_groups() {
+            let (squares, mut solves) = self.before_pairs(&empties);
+            let aboves: Vec<usize> = empties.iter().map(|sq| sq + 1).collect();
+            solves |= self.groups_with(&aboves);
+            out.push(self.solution(Rule::Before, &squares, solves));
+        }
+        out
+    }
+
+    fn specialbefores(&self) -> Vec<Solution> {
+        let mut out = Vec::new();
+        for empties in self.before_groups() {
+            let columns: Vec<usize> = empties.iter().map(|sq| sq / row::COUNT).collect();
+
+            // an empty before square that is directly playable and answered by a claimeven
+            for &special in &empties {
+                let special_col = special / row::COUNT;
+                if self.playable(special_col) != Some(special) || Self::is_even(special) {
+                    continue;
+                }
+                let others: Vec<usize> = empties
+                    .iter()
+                    .copied()
+                    .filter(|&sq| sq != special)
+                    .collect();
+                let (squares, solves) = self.before_pairs(&others);
+
+                for col in (0..column::COUNT).filter(|col| !columns.contains(col)) {
+                    let Some(playable) = self.playable(col) else {
+                        continue;
+                    };
+
+                    let mut aboves: Vec<usize> = others.iter().map(|sq| sq + 1).collect();
+                    aboves.push(playable);
+                    let special_solves =
+                        solves | self.groups_with(&[special, playable]) | self.groups_with(&aboves);
+
+                    let mut special_squares = squares.clone();
+                    special_squares.extend([special, playable]);
+                    out.push(self.solution(Rule::Specialbefore, &special_squares, special_solves));
+                }
+            }
+        }
+        out
+    }
+
+    /// Checks the second player has an answer to every move outside the given squares:
+    /// the rest of each column must split into follow-up pairs, directly above each other,
+    /// except for directly playable squares, which are paired up across columns.
+    /// Returns those directly playable squares, or None if there is no such answer.
+    fn follow_ups(&self, used: u128) -> Option<Vec<usize>> {
+        fn pairs_up(squares: &[usize]) -> bool {
+            squares.len().is_multiple_of(2) && squares.chunks(2).all(|pair| pair[1] == pair[0] + 1)
+        }
+
+        let mut singles = Vec::new();
+        for col in 0..column::COUNT {
+            let unused: Vec<usize> = (self.heights[col]..row::COUNT)
+                .map(|row| Self::square(col, row))
+                .filter(|sq| used >> sq & 1 == 0)
+                .collect();
+
+            if pairs_up(&unused) {
+                continue;
+            }
+            if unused[0] == Self::square(col, self.heights[col]) && pairs_up(&unused[1..]) {
+                singles.push(unused[0]);
+            } else {
+                return None;
+            }
+        }
+
+        singles.len().is_multiple_of(2).then_some(singles)
+    }
+
+    fn all_solutions(&self) -> Vec<Solution> {
+        [
+            self.claimevens(),
+            self.baseinverses(),
+            self.verticals(),
+            self.afterevens(),
+            self.lowinverses(),
+            self.highinverses(),
+            self.baseclaims(),
+            self.befores(),
+            self.specialbefores(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|solution| solution.solves != 0)
+        .collect()
+    }
+}
+
+/// Tries to prove, with Allis's rules and no brute-force search, that the second player
+/// can at least draw the given position.
+/// Only applies when the first player is to move and has not already won,
+/// and not on a cylinder, under misère rules, with blockers or when won by anything but
+/// lines of four, where the rules do not hold.
+pub fn prove_second_player<B: Board>(board: &B) -> Option<Proof> {
+    if B::CYLINDER
+        || B::MISERE
+        || B::win_masks() != lines::Lines::<7, 6, 4>::MASKS
+        || lines::blocker_mask(board) != 0
+        || board.curr_player() != Token::START
+    {
+        return None;
+    }
+
+    let position = Position::new(board);
+    if lines::LINE_MASKS
+        .iter()
+        .any(|mask| mask & position.yellow == *mask)
+    {
+        return None;
+    }
+
+    let solutions = position.all_solutions();
+    let mut chosen = Vec::new();
+    let singles = cover(&position, &solutions, position.groups, &mut chosen)?;
+
+    let mut solutions: Vec<Solution> = chosen.into_iter().map(|i| solutions[i].clone()).collect();
+    for pair in singles.chunks(2) {
+        solutions.push(position.solution(Rule::Baseinverse, pair, 0));
+    }
+    Some(Proof {
+        solutions,
+        groups: position.groups,
+    })
+}
+
+/// Backtracking search for a set of pairwise compatible solutions covering every group
+/// in `uncovered`, always branching on the group with the fewest candidate solutions.
+/// Returns the directly playable squares left to pair up with baseinverses.
+fn cover(
+    position: &Position,
+    solutions: &[Solution],
+    uncovered: u128,
+    chosen: &mut Vec<usize>,
+) -> Option<Vec<usize>> {
+    if uncovered == 0 {
+        let used = chosen
+            .iter()
+            .fold(0, |used, &i| used | solutions[i].squares);
+        return position.follow_ups(used);
+    }
+
+    let usable: Vec<usize> = (0..solutions.len())
+        .filter(|&i| {
+            solutions[i].solves & uncovered != 0
+                && chosen
+                    .iter()
+                    .all(|&j| solutions[i].compatible(&solutions[j]))
+        })
+        .collect();
+
+    let mut best: Option<Vec<usize>> = None;
+    for group in (0..lines::COUNT).filter(|i| uncovered >> i & 1 == 1) {
+        let candidates: Vec<usize> = usable
+            .iter()
+            .copied()
+            .filter(|&i| solutions[i].solves >> group & 1 == 1)
+            .collect();
+        if best
+            .as_ref()
+            .is_none_or(|best| candidates.len() < best.len())
+        {
+            best = Some(candidates);
+        }
+    }
+
+    for i in best.unwrap() {
+        chosen.push(i);
+        if let Some(singles) = cover(
+            position,
+            solutions,
+            uncovered & !solutions[i].solves,
+            chosen,
+        ) {
+            return Some(singles);
+        }
+        chosen.pop();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::board::bit_board::BitBoard;
+    use crate::random::Rng;
+
+    #[test]
+    fn claimevens_prove_position() {
+        let board: BitBoard = BitBoard::read(
+            "|RY.Y...|
+             |YR.R...|
+             |YY.YY.R|
+             |RR.YR.R|
+             |RR.RYRY|
+             |YR.RYYY|",
+        );
+        let proof = prove_second_player(&board).expect("no proof found");
+        assert!(
+            proof
+                .solutions
+                .iter()
+                .all(|solution| solution.rule == Rule::Claimeven)
+        );
+        assert_eq!(proof.coverage().len(), proof.groups.count_ones() as usize);
+        assert!(
+            proof
+                .explain()
+                .contains("refuted by Claimeven on (2, 2) (2, 3)")
+        );
+    }
+
+    #[test]
+    fn no_proof_when_first_player_wins() {
+        let board: BitBoard = BitBoard::read(crate::test_positions::EASY_1.board);
+        assert!(prove_second_player(&board).is_none());
+    }
+
+    #[test]
+    fn proofs_are_sound() {
+        let mut rng = Rng::seeded(29);
+        let mut proven = 0;
+
+        for _ in 0..400 {
+            let mut board: BitBoard = BitBoard::EMPTY;
+            let mut curr = Token::START;
+            let mut over = false;
+            // leave 14 empty squares for brute force to check
+            for _ in 0..28 {
+                let open: Vec<column::Idx> = column::IDXS
+                    .into_iter()
+                    .filter(|col| board.can_place(col))
+                    .collect();
+                let cell = board.place(rng.choose(&open).unwrap(), &curr).unwrap();
+                if board.won_at(&cell) {
+                    over = true;
+                    break;
+                }
+                curr = curr.next();
+            }
+            if over {
+                continue;
+            }
+
+            if let Some(proof) = prove_second_player(&board) {
+                proven += 1;
+                assert_ne!(
+                    minimax_cached(board.clone(), 14, Token::START),
+                    Some(Token::START),
+                    "unsound proof of {}{}",
+                    board.to_string(),
+                    proof.explain()
+                );
+            }
+        }
+        assert!(proven > 0, "no positions were proven");
+    }
+}
@@ -0,0 +1,250 @@
+use crate::basic::*;
+use crate::board::{Board, CloneBoard, GameStatus};
+use crate::random::Rng;
+
+/// Monte Carlo tree search player, using UCT to select which moves to explore.
+/// Plays imperfectly, but its strength scales with the number of playouts.
+#[derive(Clone, Debug)]
+pub struct Mcts {
+    /// The number of playouts (select, expand, simulate, backpropagate) per move.
+    pub playouts: usize,
+    /// The UCT exploration constant. Higher values explore less visited moves more often.
+    pub exploration: f64,
+    rng: Rng,
+}
+
+/// A node of the search tree, stored in a flat arena.
+struct Node<B, const W: usize, const H: usize> {
+    board: B,
+    /// The move that led to this node, None for the root.
+    col: Option<column::Idx<W>>,
+    /// The player that made the move that led to this node.
+    moved: Token,
+    /// Set when the move leading to this node ended the game.
+    terminal: Option<Option<Token>>,
+    children: Vec<usize>,
+    unexpanded: Vec<(B, Cell<W, H>)>,
+    visits: f64,
+    /// Sum of results from the view of `moved`: 1 for a win, 0.5 for a draw.
+    score: f64,
+}
+
+impl<B, const W: usize, const H: usize> Node<B, W, H> {
+    fn new<const K: usize>(
+        board: B,
+        col: Option<column::Idx<W>>,
+        moved: Token,
+        terminal: Option<Option<Token>>,
+    ) -> Self
+    where
+        B: CloneBoard<W, H, K>,
+    {
+        let unexpanded = match terminal {
+            Some(_) => Vec::new(),
+            None => board.next_boards(&moved.next()).collect(),
+        };
+        Node {
+            board,
+            col,
+            moved,
+            terminal,
+            children: Vec::new(),
+            unexpanded,
+            visits: 0.0,
+            score: 0.0,
+        }
+    }
+}
+
+impl Mcts {
+    /// The theoretical UCT exploration constant, sqrt(2).
+    pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+    pub fn new(playouts: usize, exploration: f64, seed: u64) -> Self {
+        Mcts {
+            playouts,
+            exploration,
+            rng: Rng::seeded(seed),
+        }
+    }
+
+    /// Searches from the given board with `curr` to play, returning the most visited move.
+    /// Returns None if the board is full.
+    pub fn best_move<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K>>(
+        &mut self,
+        board: &B,
+        curr: Token,
+    ) -> Option<column::Idx<W>> {
+        let mut tree = vec![Node::new(board.clone(), None, curr.next(), None)];
+        if tree[0].unexpanded.is_empty() {
+            return None;
+        }
+
+        for _ in 0..self.playouts {
+            let mut path = vec![0];
+            let mut idx = 0;
+
+            // selection
+            while tree[idx].terminal.is_none()
+                && tree[idx].unexpanded.is_empty()
+                && !tree[idx].children.is_empty()
+            {
+                idx = self.select_child(&tree, idx);
+                path.push(idx);
+            }
+
+            // expansion
+            if tree[idx].terminal.is_none() && !tree[idx].unexpanded.is_empty() {
+                let i = self.rng.below(tree[idx].unexpanded.len());
+                let (next_board, cell) = tree[idx].unexpanded.swap_remove(i);
+                let moved = tree[idx].moved.next();
+                let terminal = match next_board.status(&cell, moved) {
+                    GameStatus::Won(winner) => Some(Some(winner)),
+                    GameStatus::Drawn => Some(None),
+                    GameStatus::Ongoing => None,
+                };
+
+                tree.push(Node::new(next_board, Some(cell.col), moved, terminal));
+                let child = tree.len() - 1;
+                tree[idx].children.push(child);
+                idx = child;
+                path.push(idx);
+            }
+
+            // simulation
+            let result = match tree[idx].terminal {
+                Some(result) => result,
+                None => self.playout(&tree[idx].board, tree[idx].moved.next()),
+            };
+
+            // backpropagation
+            for &i in &path {
+                let node = &mut tree[i];
+                node.visits += 1.0;
+                node.score += match result {
+                    Some(winner) if winner == node.moved => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+            }
+        }
+
+        tree[0]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| tree[a].visits.total_cmp(&tree[b].visits))
+            .and_then(|&child| tree[child].col)
+    }
+
+    /// Picks the child of `parent` with the highest UCT value.
+    fn select_child<B, const W: usize, const H: usize>(
+        &self,
+        tree: &[Node<B, W, H>],
+        parent: usize,
+    ) -> usize {
+        let log_visits = tree[parent].visits.ln();
+        let uct = |child: usize| {
+            let node = &tree[child];
+            node.score / node.visits + self.exploration * (log_visits / node.visits).sqrt()
+        };
+
+        *tree[parent]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| uct(a).total_cmp(&uct(b)))
+            .unwrap()
+    }
+
+    /// Plays uniformly random moves from the given board until the game ends,
+    /// returning the winner.
+    fn playout<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K>>(
+        &mut self,
+        board: &B,
+        curr: Token,
+    ) -> Option<Token> {
+        let mut board = board.clone();
+        let mut curr = curr;
+        let mut open = Vec::with_capacity(W);
+
+        loop {
+            open.clear();
+            open.extend(
+                column::Idx::<W>::ALL
+                    .iter()
+                    .filter(|col| board.can_place(col)),
+            );
+            let col = self.rng.choose(&open)?;
+
+            let cell = board.place(col, &curr).unwrap();
+            if board.won_at(&cell) {
+                return Some(B::line_winner(curr));
+            }
+            curr = curr.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{array_board::ArrayBoard, bit_board::BitBoard, misere::Misere};
+
+    #[test]
+    fn takes_immediate_win() {
+        let board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |RRR....|
+             |YYY....|",
+        );
+        let mut mcts = Mcts::new(500, Mcts::DEFAULT_EXPLORATION, 0);
+        assert_eq!(
+            mcts.best_move(&board, Token::Yellow),
+            Some(column::Idx::raw(3))
+        );
+    }
+
+    #[test]
+    fn blocks_immediate_loss() {
+        let board: ArrayBoard = ArrayBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |Y......|
+             |YRRR..Y|",
+        );
+        let mut mcts = Mcts::new(2000, Mcts::DEFAULT_EXPLORATION, 0);
+        assert_eq!(
+            mcts.best_move(&board, Token::Yellow),
+            Some(column::Idx::raw(4))
+        );
+    }
+
+    #[test]
+    fn avoids_completing_a_line_under_misere() {
+        let board: Misere<BitBoard> = Misere(BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |RRR....|
+             |YYY....|",
+        ));
+        let mut mcts = Mcts::new(500, Mcts::DEFAULT_EXPLORATION, 0);
+        let col = mcts.best_move(&board, Token::Yellow);
+        assert!(col.is_some());
+        assert_ne!(col, Some(column::Idx::raw(3)));
+    }
+
+    #[test]
+    fn seeded_is_deterministic() {
+        let board: BitBoard = BitBoard::read(crate::test_positions::MEDIUM_0.board);
+        let curr = board.curr_player();
+        let a = Mcts::new(300, Mcts::DEFAULT_EXPLORATION, 7).best_move(&board, curr);
+        let b = Mcts::new(300, Mcts::DEFAULT_EXPLORATION, 7).best_move(&board, curr);
+        assert_eq!(a, b);
+    }
+}
@@ -1,22 +1,57 @@
 use crate::algorithms;
+use crate::algorithms::pruning::{NoPruning, PruningRule};
 use crate::basic::*;
 use crate::board::{CloneBoard, MutBoard};
 
-pub fn minimax_mut<B: MutBoard>(board: &mut B, depth: usize, curr: Token) -> Option<Token> {
-    if depth == 0 {
+pub fn minimax_mut<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    minimax_mut_with(board, depth, curr, &NoPruning)
+}
+
+/// `minimax_mut`, cutting off any position decided by the given pruning rule.
+pub fn minimax_mut_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K>,
+    R: PruningRule,
+>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+    rule: &R,
+) -> Option<Token> {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
         return None;
     }
 
+    if let Some(result) = rule.decide(board, curr, depth) {
+        return result;
+    }
+
+    let next_depth = if rule.extends(board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
     let mut losing = true;
 
-    for col in column::IDXS {
+    for col in column::Idx::<W>::ALL {
         if let Some(cell) = board.place(&col, &curr) {
             if board.won_at(&cell) {
                 board.unplace(&cell);
-                return Some(curr);
+                if B::line_winner(curr) == curr {
+                    return Some(curr);
+                }
+                // completing a line under misère rules loses, so leaves `losing` as it is
+                continue;
             }
 
-            if let Some(winner) = minimax_mut(board, depth - 1, curr.next()) {
+            if let Some(winner) = minimax_mut_with(board, next_depth, curr.next(), rule) {
                 if winner == curr {
                     board.unplace(&cell);
                     return Some(curr);
@@ -31,19 +66,53 @@ pub fn minimax_mut<B: MutBoard>(board: &mut B, depth: usize, curr: Token) -> Opt
     if losing { Some(curr.next()) } else { None }
 }
 
-pub fn minimax_clone<B: CloneBoard>(board: B, depth: usize, curr: Token) -> Option<Token> {
-    if depth == 0 {
+pub fn minimax_clone<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K>>(
+    board: B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    minimax_clone_with(board, depth, curr, &NoPruning)
+}
+
+/// `minimax_clone`, cutting off any position decided by the given pruning rule.
+pub fn minimax_clone_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K>,
+    R: PruningRule,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+    rule: &R,
+) -> Option<Token> {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
         return None;
     }
 
+    if let Some(result) = rule.decide(&board, curr, depth) {
+        return result;
+    }
+
+    let next_depth = if rule.extends(&board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
     let mut losing = true;
 
     for (next_board, cell) in board.next_boards(&curr) {
         if next_board.won_at(&cell) {
-            return Some(curr);
+            if B::line_winner(curr) == curr {
+                return Some(curr);
+            }
+            // completing a line under misère rules loses, so leaves `losing` as it is
+            continue;
         }
 
-        if let Some(winner) = minimax_clone(next_board, depth - 1, curr.next()) {
+        if let Some(winner) = minimax_clone_with(next_board, next_depth, curr.next(), rule) {
             if winner == curr {
                 return Some(curr);
             }
@@ -59,7 +128,7 @@ pub fn minimax_clone<B: CloneBoard>(board: B, depth: usize, curr: Token) -> Opti
 mod mut_tests {
     use super::*;
     use crate::board::{
-        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
     };
 
     fn run_minimax_mut<B: MutBoard>(mut board: B, depth: usize) -> Option<Token> {
@@ -76,13 +145,23 @@ mod mut_tests {
         BitBoard,
         SymmBoard
     );
+
+    #[test]
+    fn solves_small_boards_misere() {
+        let mut board = Misere(BitBoard::<4, 3, 3>::EMPTY);
+        assert_eq!(
+            minimax_mut(&mut board, 12, Token::START),
+            Some(Token::START.next())
+        );
+        assert_eq!(board, Misere(BitBoard::EMPTY));
+    }
 }
 
 #[cfg(test)]
 mod clone_tests {
     use super::*;
     use crate::board::{
-        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
     };
 
     make_easy_tests!(
@@ -94,4 +173,12 @@ mod clone_tests {
         BitBoard,
         SymmBoard
     );
+
+    #[test]
+    fn solves_small_boards_misere() {
+        assert_eq!(
+            minimax_clone(Misere(BitBoard::<3, 3, 3>::EMPTY), 9, Token::START),
+            Some(Token::START)
+        );
+    }
 }
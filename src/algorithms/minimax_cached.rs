@@ -1,38 +1,109 @@
+use crate::algorithms::pruning::{NoPruning, PruningRule};
 use crate::basic::*;
 use crate::board::CloneBoard;
 use hashbrown::HashMap;
 use std::hash::{Hash, RandomState};
 
-pub fn minimax_cached<B: CloneBoard + Hash>(board: B, depth: usize, curr: Token) -> Option<Token> {
+/// A cached search result, with the depth it was searched to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub result: Option<Token>,
+    pub depth: usize,
+}
+
+impl CacheEntry {
+    /// Returns the result a search to the given depth would give, if this entry determines it.
+    /// A win found within `self.depth` plies is also found by any deeper search,
+    /// and no win within `self.depth` plies means no win within fewer.
+    pub fn result_at(&self, depth: usize) -> Option<Option<Token>> {
+        match self.result {
+            Some(_) if depth >= self.depth => Some(self.result),
+            None if depth <= self.depth => Some(None),
+            _ => None,
+        }
+    }
+}
+
+/// Cache of search results, keyed by board.
+pub type Cache<B> = HashMap<B, CacheEntry>;
+
+pub fn minimax_cached<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    minimax_cached_with(board, depth, curr, &NoPruning)
+}
+
+/// `minimax_cached`, cutting off any position decided by the given pruning rule.
+pub fn minimax_cached_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+    rule: &R,
+) -> Option<Token> {
     let mut cache = HashMap::new();
-    minimax_cached_helper(board, depth, curr, &mut cache)
+    minimax_cached_helper(board, depth, curr, &mut cache, rule)
 }
 
-pub fn minimax_cached_helper<B: CloneBoard + Hash>(
+pub fn minimax_cached_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
     board: B,
     depth: usize,
     curr: Token,
-    cache: &mut HashMap<B, Option<Token>>,
+    cache: &mut Cache<B>,
+    rule: &R,
 ) -> Option<Token> {
-    if depth == 0 {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
         return None;
     }
 
-    if let Some(cached_result) = cache.get(&board) {
-        return *cached_result;
+    if let Some(cached_result) = cache.get(&board).and_then(|entry| entry.result_at(depth)) {
+        return cached_result;
+    }
+
+    if let Some(result) = rule.decide(&board, curr, depth) {
+        cache.insert(board, CacheEntry { result, depth });
+        return result;
     }
 
+    let next_depth = if rule.extends(&board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
     let mut out = None;
 
     let mut losing = true;
 
     for (next_board, cell) in board.next_boards(&curr) {
         if next_board.won_at(&cell) {
-            out = Some(curr);
-            break;
+            if B::line_winner(curr) == curr {
+                out = Some(curr);
+                break;
+            }
+            // completing a line under misère rules loses, so leaves `losing` as it is
+            continue;
         }
 
-        match minimax_cached_helper(next_board, depth - 1, curr.next(), cache) {
+        match minimax_cached_helper(next_board, next_depth, curr.next(), cache, rule) {
             None => losing = false,
             Some(winner) if winner == curr => {
                 out = Some(winner);
@@ -45,7 +116,7 @@ pub fn minimax_cached_helper<B: CloneBoard + Hash>(
         out = Some(curr.next());
     }
 
-    cache.insert(board, out);
+    cache.insert(board, CacheEntry { result: out, depth });
     out
 }
 
@@ -53,7 +124,7 @@ pub fn minimax_cached_helper<B: CloneBoard + Hash>(
 mod tests {
     use super::*;
     use crate::board::{
-        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
     };
 
     make_easy_tests!(
@@ -75,5 +146,85 @@ mod tests {
         BitBoard
     );
 
+    #[test]
+    fn solves_small_boards() {
+        let start = Token::START;
+        assert_eq!(minimax_cached(BitBoard::<4, 4, 4>::EMPTY, 16, start), None);
+        assert_eq!(minimax_cached(BitBoard::<3, 3, 3>::EMPTY, 9, start), None);
+        assert_eq!(
+            minimax_cached(BitBoard::<4, 3, 3>::EMPTY, 12, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_cached(ArrayBoard::<4, 3, 3>::EMPTY, 12, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_cached(SymmBoard::<4, 3, 3>::EMPTY, 12, start),
+            Some(start)
+        );
+    }
 
+    #[test]
+    fn connect_five_open_four() {
+        // Yellow's open four on the bottom row can be finished at either end
+        let board = BitBoard::<8, 7, 5>::read(
+            "|........|
+             |..RRR...|
+             |..YYYY..|",
+        );
+        let curr = board.curr_player();
+        assert_eq!(curr, Token::Red);
+        assert_eq!(minimax_cached(board.clone(), 2, curr), Some(Token::Yellow));
+        assert_eq!(minimax_cached(board, 1, curr), None);
+    }
+
+    #[test]
+    fn misere_avoids_lines() {
+        let board = BitBoard::<3, 3, 3>::read(
+            "|...|
+             |RR.|
+             |YY.|",
+        );
+        // completing row 0 wins, and is the one move to avoid under misère rules
+        assert_eq!(
+            minimax_cached(board.clone(), 1, Token::Yellow),
+            Some(Token::Yellow)
+        );
+        assert_eq!(minimax_cached(Misere(board), 1, Token::Yellow), None);
+    }
+
+    #[test]
+    fn solves_small_boards_misere() {
+        let start = Token::START;
+        assert_eq!(
+            minimax_cached(Misere(BitBoard::<3, 3, 3>::EMPTY), 9, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_cached(Misere(BitBoard::<4, 3, 3>::EMPTY), 12, start),
+            Some(start.next())
+        );
+        assert_eq!(
+            minimax_cached(Misere(ArrayBoard::<4, 4, 4>::EMPTY), 16, start),
+            Some(start.next())
+        );
+    }
+
+    #[test]
+    fn entries_respect_depth() {
+        let win = CacheEntry {
+            result: Some(Token::Red),
+            depth: 5,
+        };
+        assert_eq!(win.result_at(7), Some(Some(Token::Red)));
+        assert_eq!(win.result_at(3), None);
+
+        let unknown = CacheEntry {
+            result: None,
+            depth: 5,
+        };
+        assert_eq!(unknown.result_at(3), Some(None));
+        assert_eq!(unknown.result_at(7), None);
+    }
 }
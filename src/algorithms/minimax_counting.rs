@@ -1,42 +1,92 @@
+use crate::algorithms::minimax_cached::{Cache, CacheEntry};
+use crate::algorithms::pruning::{NoPruning, PruningRule};
 use crate::basic::*;
 use crate::board::CloneBoard;
 use binary_heap_plus::*;
-use std::hash::Hash;
 use hashbrown::HashMap;
+use std::hash::Hash;
+
+pub fn minimax_counting<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    minimax_counting_with(board, depth, curr, &NoPruning)
+}
 
-pub fn minimax_counting<B: CloneBoard + Hash>(
+/// `minimax_counting`, cutting off any position decided by the given pruning rule.
+pub fn minimax_counting_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
     board: B,
     depth: usize,
     curr: Token,
+    rule: &R,
 ) -> Option<Token> {
     let mut cache = HashMap::new();
-    minimax_counting_helper(board, depth, curr, &mut cache)
+    minimax_counting_helper(board, depth, curr, &mut cache, rule)
 }
 
-pub fn minimax_counting_helper<B: CloneBoard + Hash>(
+pub fn minimax_counting_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
     board: B,
     depth: usize,
     curr: Token,
-    cache: &mut HashMap<B, Option<Token>>,
+    cache: &mut Cache<B>,
+    rule: &R,
 ) -> Option<Token> {
-    if depth == 0 {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
         return None;
     }
 
-    if let Some(cached_result) = cache.get(&board) {
-        return *cached_result;
+    if let Some(cached_result) = cache.get(&board).and_then(|entry| entry.result_at(depth)) {
+        return cached_result;
+    }
+
+    if let Some(result) = rule.decide(&board, curr, depth) {
+        cache.insert(board, CacheEntry { result, depth });
+        return result;
     }
 
+    let next_depth = if rule.extends(&board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
     // BinaryHeap to sort possible boards by heuristic
-    let mut nexts =
-        BinaryHeap::with_capacity_by_key(column::COUNT, |(next_board, cell, info)| *info);
+    let mut nexts = BinaryHeap::with_capacity_by_key(W, |(next_board, cell, info)| *info);
 
     for (next_board, cell) in board.next_boards(&curr) {
         match next_board.count_adjacent_at(&cell) {
-            // found a win
+            // found a line, which wins unless under misère rules
             None => {
-                cache.insert(next_board, Some(curr));
-                return Some(curr);
+                let winner = B::line_winner(curr);
+                // the game is over, so the result holds at any depth
+                cache.insert(
+                    next_board,
+                    CacheEntry {
+                        result: Some(winner),
+                        depth: 0,
+                    },
+                );
+                if winner == curr {
+                    return Some(curr);
+                }
             }
             // add the  board
             Some(info) => nexts.push((next_board, cell, info)),
@@ -46,10 +96,16 @@ pub fn minimax_counting_helper<B: CloneBoard + Hash>(
     let mut losing = true;
 
     for (next_board, cell, _) in nexts.into_iter_sorted() {
-        match minimax_counting_helper(next_board, depth - 1, curr.next(), cache) {
+        match minimax_counting_helper(next_board, next_depth, curr.next(), cache, rule) {
             None => losing = false,
             Some(winner) if winner == curr => {
-                cache.insert(board, Some(winner));
+                cache.insert(
+                    board,
+                    CacheEntry {
+                        result: Some(winner),
+                        depth,
+                    },
+                );
                 return Some(winner);
             }
             _ => (),
@@ -57,7 +113,7 @@ pub fn minimax_counting_helper<B: CloneBoard + Hash>(
     }
 
     let result = if losing { Some(curr.next()) } else { None };
-    cache.insert(board, result);
+    cache.insert(board, CacheEntry { result, depth });
     result
 }
 
@@ -65,7 +121,7 @@ pub fn minimax_counting_helper<B: CloneBoard + Hash>(
 mod tests {
     use super::*;
     use crate::board::{
-        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
     };
 
     make_easy_tests!(
@@ -87,4 +143,16 @@ mod tests {
         BitBoard
     );
 
+    #[test]
+    fn solves_small_boards_misere() {
+        let start = Token::START;
+        assert_eq!(
+            minimax_counting(Misere(BitBoard::<3, 3, 3>::EMPTY), 9, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_counting(Misere(SymmBoard::<4, 3, 3>::EMPTY), 12, start),
+            Some(start.next())
+        );
+    }
 }
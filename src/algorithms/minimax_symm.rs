@@ -1,4 +1,5 @@
-use crate::algorithms::minimax_cached::minimax_cached_helper;
+use crate::algorithms::minimax_cached::{Cache, CacheEntry, minimax_cached_helper};
+use crate::algorithms::pruning::{NoPruning, PruningRule};
 use crate::basic::*;
 use crate::board::{Board, CloneBoard};
 use hashbrown::HashMap;
@@ -6,26 +7,36 @@ use std::hash::Hash;
 
 /// Type to store the difference in height of each column with its reflection,
 /// to efficiently compute when board is symmetrical.
+/// Only the left half is used, the rest stays zero.
 ///
-/// i.e. symm_diff[i] = column[6-i] - column[i].height()
-type SymmDiff = [isize; 3];
+/// i.e. symm_diff[i] = column[W-1-i].height() - column[i].height()
+type SymmDiff<const W: usize> = [isize; W];
 
 /// Creates a SymmDiffs for the given board.
-/// Returns None if the board is irreversably asymmetrical.
-fn make_diffs<B: Board>(board: &B) -> Option<SymmDiff> {
-    let mut diffs: [isize; 3] = [0; 3];
-    for &col_l in column::IDXS[0..3].iter() {
+/// Returns None if the board is irreversably asymmetrical,
+/// as it always is if its blockers are not symmetrical, since blockers never move,
+/// or if it is won differently in the mirror image.
+fn make_diffs<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+) -> Option<SymmDiff<W>> {
+    if !B::mirror_symmetric() {
+        return None;
+    }
+
+    let mut diffs = [0; W];
+    for &col_l in column::Idx::<W>::ALL[..W / 2].iter() {
         let col_r = col_l.flipped();
 
-        for row in row::BOTTOM_UP {
-            let token_l = board.get(&Cell { col: col_l, row });
-            let token_r = board.get(&Cell { col: col_r, row });
-            match (token_l, token_r) {
+        for row in row::Idx::<H>::BOTTOM_UP {
+            let piece_l = board.piece(&Cell { col: col_l, row });
+            let piece_r = board.piece(&Cell { col: col_r, row });
+            match (piece_l, piece_r) {
                 (None, None) => break,
+                (None, Some(Piece::Blocker)) | (Some(Piece::Blocker), None) => return None,
                 (None, Some(_)) => diffs[usize::from(col_l)] += 1,
                 (Some(_), None) => diffs[usize::from(col_l)] -= 1,
-                (Some(token_l), Some(token_r)) => {
-                    if token_l != token_r {
+                (Some(piece_l), Some(piece_r)) => {
+                    if piece_l != piece_r {
                         return None;
                     }
                 }
@@ -36,36 +47,67 @@ fn make_diffs<B: Board>(board: &B) -> Option<SymmDiff> {
     Some(diffs)
 }
 
-pub fn minimax_symm<B: CloneBoard + Hash>(board: B, depth: usize, curr: Token) -> Option<Token> {
+pub fn minimax_symm<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    minimax_symm_with(board, depth, curr, &NoPruning)
+}
+
+/// `minimax_symm`, cutting off any position decided by the given pruning rule.
+pub fn minimax_symm_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+    rule: &R,
+) -> Option<Token> {
     let mut cache = HashMap::new();
-    if let Some(diffs) = make_diffs(&board) {
-        minimax_symm_helper(board, depth, curr, &mut cache, diffs)
+    if B::CYLINDER {
+        cylinder_helper(board.canonical(), depth, curr, &mut cache, rule)
+    } else if let Some(diffs) = make_diffs(&board) {
+        minimax_symm_helper(board, depth, curr, &mut cache, diffs, rule)
     } else {
-        minimax_cached_helper(board, depth, curr, &mut cache)
+        minimax_cached_helper(board, depth, curr, &mut cache, rule)
     }
 }
 
 /// Updates the given diff considering the token just placed at `cell`.
 /// Returns None if the board is irreversibly asymmetrical
-fn next_diffs<B: Board>(board: &B, cell: &Cell, diffs: SymmDiff) -> Option<SymmDiff> {
-    if cell.col == column::Idx::CENTRE {
+fn next_diffs<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    cell: &Cell<W, H>,
+    diffs: SymmDiff<W>,
+) -> Option<SymmDiff<W>> {
+    if cell.col == cell.col.flipped() {
         return Some(diffs);
     }
 
     let mut new_diffs = diffs;
-    let token = board.get(cell)?;
+    let piece = board.piece(cell)?;
     let flipped = Cell {
         col: cell.col.flipped(),
         row: cell.row,
     };
 
-    if let Some(flipped) = board.get(&flipped)
-        && token != flipped
+    if let Some(flipped) = board.piece(&flipped)
+        && piece != flipped
     {
         return None;
     }
 
-    if usize::from(cell.col) < 3 {
+    if cell.col < flipped.col {
         new_diffs[usize::from(cell.col)] -= 1;
     } else {
         new_diffs[usize::from(flipped.col)] += 1;
@@ -74,53 +116,78 @@ fn next_diffs<B: Board>(board: &B, cell: &Cell, diffs: SymmDiff) -> Option<SymmD
     Some(new_diffs)
 }
 
-fn next_boards<B: CloneBoard + Hash>(
+fn next_boards<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K> + Hash>(
     board: &B,
     curr: &Token,
-    diffs: SymmDiff,
-) -> Vec<(Option<SymmDiff>, B, Cell)> {
-    match diffs {
-        [0, 0, 0] => column::IDXS[0..=3]
+    diffs: SymmDiff<W>,
+) -> Vec<(Option<SymmDiff<W>>, B, Cell<W, H>)> {
+    // a symmetric board only needs the columns up to the middle
+    if diffs == [0; W] {
+        column::Idx::<W>::ALL[..W.div_ceil(2)]
             .iter()
-            .filter_map(|col| board.clone_and_place(&col, curr))
+            .filter_map(|col| board.clone_and_place(col, curr))
             .map(|(b, p)| (next_diffs(&b, &p, diffs), b, p))
-            .collect(),
-
-        _ => board
+            .collect()
+    } else {
+        board
             .next_boards(curr)
             .map(|(b, p)| (next_diffs(&b, &p, diffs), b, p))
-            .collect(),
+            .collect()
     }
 }
 
-fn minimax_symm_helper<B: CloneBoard + Hash>(
+fn minimax_symm_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
     board: B,
     depth: usize,
     curr: Token,
-    cache: &mut HashMap<B, Option<Token>>,
-    diffs: SymmDiff,
+    cache: &mut Cache<B>,
+    diffs: SymmDiff<W>,
+    rule: &R,
 ) -> Option<Token> {
-    if depth == 0 {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
         return None;
     }
 
-    if let Some(cached_result) = cache.get(&board) {
-        return *cached_result;
+    if let Some(cached_result) = cache.get(&board).and_then(|entry| entry.result_at(depth)) {
+        return cached_result;
     }
 
+    if let Some(result) = rule.decide(&board, curr, depth) {
+        cache.insert(board, CacheEntry { result, depth });
+        return result;
+    }
+
+    let next_depth = if rule.extends(&board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
     let mut out = None;
 
     let mut losing = true;
 
     for (diffs, next_board, cell) in next_boards(&board, &curr, diffs) {
         if next_board.won_at(&cell) {
-            out = Some(curr);
-            break;
+            if B::line_winner(curr) == curr {
+                out = Some(curr);
+                break;
+            }
+            // completing a line under misère rules loses, so leaves `losing` as it is
+            continue;
         }
 
         let result = match diffs {
-            None => minimax_cached_helper(next_board, depth - 1, curr.next(), cache),
-            Some(diffs) => minimax_symm_helper(next_board, depth - 1, curr.next(), cache, diffs),
+            None => minimax_cached_helper(next_board, next_depth, curr.next(), cache, rule),
+            Some(diffs) => {
+                minimax_symm_helper(next_board, next_depth, curr.next(), cache, diffs, rule)
+            }
         };
 
         match result {
@@ -137,7 +204,81 @@ fn minimax_symm_helper<B: CloneBoard + Hash>(
         out = Some(curr.next());
     }
 
-    cache.insert(board, out);
+    cache.insert(board, CacheEntry { result: out, depth });
+    out
+}
+
+/// `minimax_symm_helper` for boards on a cylinder, where every rotation of a board is
+/// equivalent to it as well as its mirror image. Takes boards in their
+/// `CloneBoard::canonical` form, and of moves leading to equivalent boards only tries the first.
+fn cylinder_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+    R: PruningRule,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+    cache: &mut Cache<B>,
+    rule: &R,
+) -> Option<Token> {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
+        return None;
+    }
+
+    if let Some(cached_result) = cache.get(&board).and_then(|entry| entry.result_at(depth)) {
+        return cached_result;
+    }
+
+    if let Some(result) = rule.decide(&board, curr, depth) {
+        cache.insert(board, CacheEntry { result, depth });
+        return result;
+    }
+
+    let next_depth = if rule.extends(&board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
+    let mut out = None;
+
+    let mut losing = true;
+    let mut tried = Vec::new();
+
+    for (next_board, cell) in board.next_boards(&curr) {
+        if next_board.won_at(&cell) {
+            if B::line_winner(curr) == curr {
+                out = Some(curr);
+                break;
+            }
+            // completing a line under misère rules loses, so leaves `losing` as it is
+            continue;
+        }
+
+        let next_board = next_board.canonical();
+        if tried.contains(&next_board) {
+            continue;
+        }
+        tried.push(next_board.clone());
+
+        match cylinder_helper(next_board, next_depth, curr.next(), cache, rule) {
+            None => losing = false,
+            Some(winner) if winner == curr => {
+                out = Some(curr);
+                break;
+            }
+            _ => (),
+        }
+    }
+
+    if out.is_none() && losing {
+        out = Some(curr.next());
+    }
+
+    cache.insert(board, CacheEntry { result: out, depth });
     out
 }
 
@@ -145,7 +286,7 @@ fn minimax_symm_helper<B: CloneBoard + Hash>(
 mod tests {
     use super::*;
     use crate::board::{
-        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
     };
 
     make_easy_tests!(
@@ -166,4 +307,120 @@ mod tests {
         SymmBoard,
         BitBoard
     );
+
+    #[test]
+    fn solves_even_and_odd_widths() {
+        let start = Token::START;
+        assert_eq!(minimax_symm(SymmBoard::<4, 4, 4>::EMPTY, 16, start), None);
+        assert_eq!(minimax_symm(BitBoard::<3, 3, 3>::EMPTY, 9, start), None);
+        assert_eq!(
+            minimax_symm(SymmBoard::<4, 3, 3>::EMPTY, 12, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_symm(BitBoard::<5, 3, 3>::EMPTY, 15, start),
+            minimax_cached_helper(
+                BitBoard::<5, 3, 3>::EMPTY,
+                15,
+                start,
+                &mut HashMap::new(),
+                &NoPruning
+            )
+        );
+    }
+
+    #[test]
+    fn solves_small_boards_misere() {
+        let start = Token::START;
+        assert_eq!(
+            minimax_symm(Misere(SymmBoard::<3, 3, 3>::EMPTY), 9, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_symm(Misere(SymmBoard::<4, 3, 3>::EMPTY), 12, start),
+            Some(start.next())
+        );
+        assert_eq!(
+            minimax_symm(Misere(BitBoard::<4, 4, 4>::EMPTY), 16, start),
+            Some(start.next())
+        );
+        assert_eq!(
+            minimax_symm(Misere(SymmBoard::<4, 4, 3, true>::EMPTY), 16, start),
+            minimax_cached_helper(
+                Misere(SymmBoard::<4, 4, 3, true>::EMPTY),
+                16,
+                start,
+                &mut HashMap::new(),
+                &NoPruning
+            )
+        );
+    }
+
+    /// Solves the board with both `minimax_symm` and `minimax_cached`, checking they agree.
+    fn solve_blocked<const W: usize, const H: usize, const K: usize, B>(
+        board: &str,
+    ) -> Option<Token>
+    where
+        B: CloneBoard<W, H, K> + Hash,
+    {
+        let board = B::read(board);
+        let (depth, curr) = (W * H, board.curr_player());
+        let result = minimax_symm(board.clone(), depth, curr);
+        assert_eq!(
+            minimax_cached_helper(board, depth, curr, &mut HashMap::new(), &NoPruning),
+            result
+        );
+        result
+    }
+
+    #[test]
+    fn solves_boards_with_blockers() {
+        // blocking both corners keeps 4x4 drawn, but one blocker by the centre wins it for Yellow
+        assert_eq!(solve_blocked::<4, 4, 4, SymmBoard<4, 4, 4>>("|#..#|"), None);
+        assert_eq!(
+            solve_blocked::<4, 4, 4, SymmBoard<4, 4, 4>>("|.#..|"),
+            Some(Token::Yellow)
+        );
+        // and blocking the corners hands connect three on 4x3 to Red
+        assert_eq!(
+            solve_blocked::<4, 3, 3, BitBoard<4, 3, 3>>("|#..#|"),
+            Some(Token::Red)
+        );
+        assert_eq!(
+            solve_blocked::<4, 3, 3, ArrayBoard<4, 3, 3>>("|.#..|"),
+            solve_blocked::<4, 3, 3, ArrayBoard<4, 3, 3>>("|..#.|")
+        );
+        assert_eq!(
+            solve_blocked::<4, 3, 3, SymmBoard<4, 3, 3, true>>("|#...|"),
+            solve_blocked::<4, 3, 3, ArrayBoard<4, 3, 3, true>>("|..#.|")
+        );
+    }
+
+    #[test]
+    fn solves_cylinders() {
+        let start = Token::START;
+        // drawn on a flat board, but Yellow wins once lines wrap round
+        assert_eq!(
+            minimax_symm(BitBoard::<3, 3, 3, true>::EMPTY, 9, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_symm(SymmBoard::<4, 4, 4, true>::EMPTY, 16, start),
+            None
+        );
+        assert_eq!(
+            minimax_symm(SymmBoard::<4, 3, 3, true>::EMPTY, 12, start),
+            Some(start)
+        );
+        assert_eq!(
+            minimax_symm(BitBoard::<5, 3, 3, true>::EMPTY, 15, start),
+            minimax_cached_helper(
+                BitBoard::<5, 3, 3, true>::EMPTY,
+                15,
+                start,
+                &mut HashMap::new(),
+                &NoPruning
+            )
+        );
+    }
 }
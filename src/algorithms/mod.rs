@@ -1,6 +1,16 @@
 #[macro_use]
 mod testing;
+pub mod cross_check;
+pub mod evaluators;
+pub mod knowledge;
+pub mod mcts;
 pub mod minimax_basic;
 pub mod minimax_cached;
 pub mod minimax_counting;
 pub mod minimax_symm;
+pub mod negamax;
+pub mod perft;
+pub mod popout;
+pub mod pruning;
+pub mod win_in_n;
+pub mod zugzwang;
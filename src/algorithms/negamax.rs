@@ -0,0 +1,202 @@
+use crate::algorithms::evaluators::Evaluator;
+use crate::algorithms::pruning::{NoPruning, PruningRule};
+use crate::basic::*;
+use crate::board::MutBoard;
+
+/// Score of a forced win. A win found with `d` plies of depth remaining scores
+/// `WIN_SCORE + d`, so faster wins are preferred.
+pub const WIN_SCORE: isize = 1_000_000;
+
+/// Depth-limited negamax with alpha-beta pruning, scoring positions at the horizon
+/// with the given evaluator.
+/// Returns the score from the view of `curr` and the best move, or None if there are no moves.
+pub fn negamax<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K>,
+    E: Evaluator,
+>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+    eval: &E,
+) -> (isize, Option<column::Idx<W>>) {
+    negamax_with(board, depth, curr, eval, &NoPruning)
+}
+
+/// `negamax`, cutting off any position after the first move that is decided
+/// by the given pruning rule.
+pub fn negamax_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K>,
+    E: Evaluator,
+    R: PruningRule,
+>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+    eval: &E,
+    rule: &R,
+) -> (isize, Option<column::Idx<W>>) {
+    negamax_helper(board, depth, curr, eval, rule, -isize::MAX, isize::MAX)
+}
+
+/// Converts a score from `negamax` into the proven winner, if there is one.
+pub fn score_winner(score: isize, curr: Token) -> Option<Token> {
+    if score >= WIN_SCORE {
+        Some(curr)
+    } else if score <= -WIN_SCORE {
+        Some(curr.next())
+    } else {
+        None
+    }
+}
+
+fn negamax_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K>,
+    E: Evaluator,
+    R: PruningRule,
+>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+    eval: &E,
+    rule: &R,
+    mut alpha: isize,
+    beta: isize,
+) -> (isize, Option<column::Idx<W>>) {
+    if depth == 0 {
+        return (eval.evaluate(board, curr), None);
+    }
+
+    let next_depth = if rule.extends(board, curr) {
+        depth
+    } else {
+        depth - 1
+    };
+    let mut best = (-isize::MAX, None);
+
+    for col in column::Idx::<W>::CENTRED_FIRST {
+        let Some(cell) = board.place(&col, &curr) else {
+            continue;
+        };
+
+        let score = if board.won_at(&cell) {
+            // under misère rules completing a line loses, as quickly as it would have won
+            if B::line_winner(curr) == curr {
+                WIN_SCORE + depth as isize
+            } else {
+                -(WIN_SCORE + depth as isize)
+            }
+        } else if let Some(result) = rule.decide(board, curr.next(), next_depth) {
+            match result {
+                Some(winner) if winner == curr => WIN_SCORE,
+                Some(_) => -WIN_SCORE,
+                None => 0,
+            }
+        } else {
+            -negamax_helper(board, next_depth, curr.next(), eval, rule, -beta, -alpha).0
+        };
+        board.unplace(&cell);
+
+        if score > best.0 {
+            best = (score, Some(col));
+        }
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    // no moves left, the board is full
+    if best.1.is_none() {
+        return (0, None);
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::evaluators::{OpenLines, ThreatParity};
+    use crate::algorithms::pruning::DeadPosition;
+    use crate::board::{
+        Board, array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
+    };
+
+    make_easy_tests!(
+        |mut b, d| {
+            let curr = b.curr_player();
+            score_winner(negamax(&mut b, d, curr, &OpenLines).0, curr)
+        },
+        ArrayBoard,
+        BitBoard,
+        SymmBoard
+    );
+
+    #[test]
+    fn takes_immediate_win() {
+        let mut board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |.RRR...|
+             |.YYY...|",
+        );
+        let (score, col) = negamax(&mut board, 3, Token::Yellow, &ThreatParity);
+        assert!(score >= WIN_SCORE);
+        assert!(col == Some(column::Idx::raw(0)) || col == Some(column::Idx::raw(4)));
+    }
+
+    #[test]
+    fn dead_position_scores_draw() {
+        let mut board: BitBoard = BitBoard::read(
+            "|RRY.RR.|
+             |YYRRYYR|
+             |RRYYRRY|
+             |YYRRYYR|
+             |YYRRYYR|
+             |YYRRYYR|",
+        );
+        let (score, col) = negamax_with(&mut board, 3, Token::Red, &OpenLines, &DeadPosition);
+        assert_eq!(score, 0);
+        assert!(col.is_some());
+    }
+
+    #[test]
+    fn horizon_uses_heuristic() {
+        let mut board: BitBoard = BitBoard::EMPTY;
+        let (score, col) = negamax(&mut board, 1, Token::Yellow, &OpenLines);
+        assert_eq!(col, Some(column::Idx::CENTRE));
+        assert!(score > 0 && score < WIN_SCORE);
+    }
+
+    #[test]
+    fn misere_avoids_lines() {
+        let mut board = Misere(BitBoard::<3, 3, 3>::read(
+            "|...|
+             |RR.|
+             |YY.|",
+        ));
+        // completing row 0 loses at once, leaving the other two columns
+        let (score, col) = negamax(&mut board, 1, Token::Yellow, &OpenLines);
+        assert!(score > -WIN_SCORE);
+        assert!(col.is_some_and(|col| col != column::Idx::raw(2)));
+    }
+
+    #[test]
+    fn solves_small_boards_misere() {
+        let mut board = Misere(BitBoard::<3, 3, 3>::EMPTY);
+        let (score, col) = negamax(&mut board, 9, Token::START, &ThreatParity);
+        assert_eq!(score_winner(score, Token::START), Some(Token::START));
+        assert!(col.is_some());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::basic::*;
+use crate::board::array_board::ArrayBoard;
+use crate::board::bit_board::BitBoard;
+use crate::board::moves::Moves;
+use crate::board::symm_board::SymmBoard;
+use crate::board::{Board, CloneBoard};
+use hashbrown::HashMap;
+use std::hash::Hash;
+use std::time::Instant;
+
+/// The number of distinct positions in 7x6 Connect Four after each ply from the empty board,
+/// counting positions that end the game (OEIS A212693).
+pub const POSITIONS: [usize; 11] = [
+    1, 7, 49, 238, 1120, 4263, 16422, 54859, 184275, 558186, 1662623,
+];
+
+/// The counts for a single ply.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlyCounts {
+    /// The number of move sequences of this many plies, stopping at wins.
+    pub sequences: u64,
+    /// The number of distinct positions, as told apart by the board's `Eq` and `Hash`.
+    pub positions: usize,
+}
+
+/// Counts the move sequences and distinct positions reachable from the board at each ply,
+/// from 0 up to and including `ply`. Positions that have been won are counted, but not played on.
+/// With `SymmBoard`, a position and its mirror image count as one.
+pub fn perft<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K> + Hash>(
+    board: B,
+    ply: usize,
+) -> Vec<PlyCounts> {
+    let mut curr = board.curr_player();
+    // each position open to play on, with the number of sequences reaching it
+    let mut layer: HashMap<B, u64> = HashMap::new();
+    layer.insert(board, 1);
+
+    let mut counts = vec![PlyCounts {
+        sequences: 1,
+        positions: 1,
+    }];
+    for _ in 0..ply {
+        let mut next: HashMap<B, u64> = HashMap::new();
+        let mut won: HashMap<B, u64> = HashMap::new();
+        for (board, sequences) in &layer {
+            for (next_board, cell) in board.next_boards(&curr) {
+                let target = if next_board.won_at(&cell) {
+                    &mut won
+                } else {
+                    &mut next
+                };
+                *target.entry(next_board).or_insert(0) += sequences;
+            }
+        }
+
+        counts.push(PlyCounts {
+            sequences: next.values().chain(won.values()).sum(),
+            positions: next.len() + won.len(),
+        });
+        layer = next;
+        curr = curr.next();
+    }
+    counts
+}
+
+/// Runs `perft` from the empty board on every board implementation, printing the counts
+/// and throughput. Usage: `perft [ply]`, defaulting to 8 plies.
+pub fn main(args: &[String]) {
+    let ply = args
+        .first()
+        .map(|arg| arg.parse().expect("the ply should be a number"))
+        .unwrap_or(8);
+
+    fn run<B: CloneBoard + Hash>(name: &str, ply: usize) {
+        let start = Instant::now();
+        let counts = perft(B::EMPTY, ply);
+        let elapsed = start.elapsed();
+
+        println!("{name}");
+        println!("{:>4} {:>15} {:>12}", "ply", "sequences", "positions");
+        for (i, count) in counts.iter().enumerate() {
+            println!("{i:>4} {:>15} {:>12}", count.sequences, count.positions);
+        }
+        let total: usize = counts.iter().map(|count| count.positions).sum();
+        println!(
+            "{total} positions in {:.2} ms ({:.0} positions/s)\n",
+            elapsed.as_secs_f64() * 1000.0,
+            total as f64 / elapsed.as_secs_f64()
+        );
+    }
+
+    run::<ArrayBoard>("ArrayBoard", ply);
+    run::<BitBoard>("BitBoard", ply);
+    run::<SymmBoard>("SymmBoard (mirrors merged)", ply);
+    run::<Moves>("Moves", ply);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hashbrown::HashSet;
+
+    fn check_positions<B: CloneBoard + Hash>() {
+        let counts = perft(B::EMPTY, 8);
+        for (count, expected) in counts.iter().zip(POSITIONS) {
+            assert_eq!(count.positions, expected);
+        }
+    }
+
+    #[test]
+    fn array_board_matches_published_counts() {
+        check_positions::<ArrayBoard>();
+    }
+
+    #[test]
+    fn bit_board_matches_published_counts() {
+        check_positions::<BitBoard>();
+    }
+
+    /// Counts the positions at each ply that are their own mirror image.
+    fn symmetric_positions(ply: usize) -> Vec<usize> {
+        let mut curr = Token::START;
+        let mut layer = HashSet::from([<BitBoard>::EMPTY]);
+        let mut counts = vec![1];
+        for _ in 0..ply {
+            let mut next = HashSet::new();
+            let mut won = HashSet::new();
+            for board in &layer {
+                for (next_board, cell) in board.next_boards(&curr) {
+                    if next_board.won_at(&cell) {
+                        won.insert(next_board);
+                    } else {
+                        next.insert(next_board);
+                    }
+                }
+            }
+
+            let symmetric = next.iter().chain(&won);
+            counts.push(symmetric.filter(|board| board.flipped() == **board).count());
+            layer = next;
+            curr = curr.next();
+        }
+        counts
+    }
+
+    #[test]
+    fn counts_agree_across_boards() {
+        let plain = perft(<BitBoard>::EMPTY, 8);
+        assert_eq!(perft(<ArrayBoard>::EMPTY, 8), plain);
+        assert_eq!(perft(<Moves>::EMPTY, 7), plain[..=7]);
+
+        // a mirrored pair is one position on `SymmBoard`, and a symmetric position is one anyway
+        let mirrored = perft(<SymmBoard>::EMPTY, 8);
+        let symmetric = symmetric_positions(8);
+        for ply in 0..=8 {
+            assert_eq!(mirrored[ply].sequences, plain[ply].sequences);
+            assert_eq!(
+                mirrored[ply].positions,
+                (plain[ply].positions + symmetric[ply]) / 2
+            );
+        }
+        // no wins are possible before the seventh ply
+        assert_eq!(plain[6].sequences, 7u64.pow(6));
+        assert!(plain[8].sequences < 7u64.pow(8));
+    }
+}
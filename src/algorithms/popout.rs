@@ -0,0 +1,531 @@
+use crate::algorithms::minimax_cached::{Cache, CacheEntry};
+use crate::basic::*;
+use crate::board::{Board, MutBoard};
+use hashbrown::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+
+/// A move in PopOut: drop a token into a column as usual,
+/// or pop one of your own tokens from the bottom of a column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Move<const W: usize = { column::COUNT }> {
+    Drop(column::Idx<W>),
+    Pop(column::Idx<W>),
+}
+
+impl<const W: usize> Display for Move<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Drop(col) => write!(f, "drop {col}"),
+            Move::Pop(col) => write!(f, "pop {col}"),
+        }
+    }
+}
+
+/// Returns true if `curr` can pop from the column, i.e. the token at the bottom is theirs.
+pub fn can_pop<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    col: &column::Idx<W>,
+    curr: &Token,
+) -> bool {
+    let bottom = Cell {
+        col: *col,
+        row: row::Idx::BOTTOM,
+    };
+    board.get(&bottom) == Some(*curr)
+}
+
+/// Returns every legal move for `curr`: drops from the centre outwards, then pops.
+pub fn moves<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    curr: &Token,
+) -> Vec<Move<W>> {
+    let cols = column::Idx::<W>::CENTRED_FIRST;
+    let drops = cols
+        .iter()
+        .filter(|col| board.can_place(col))
+        .map(|&col| Move::Drop(col));
+    let pops = cols
+        .iter()
+        .filter(|col| can_pop(board, col, curr))
+        .map(|&col| Move::Pop(col));
+    drops.chain(pops).collect()
+}
+
+/// Returns the cells of the pieces in the column, tokens and blockers, from the bottom up.
+fn column_cells<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    col: &column::Idx<W>,
+) -> impl Iterator<Item = Cell<W, H>> {
+    row::Idx::<H>::BOTTOM_UP
+        .map(|row| Cell { col: *col, row })
+        .take_while(|cell| board.piece(cell).is_some())
+}
+
+/// Plays a legal move for `curr`, returning the winner if it completes a line.
+/// A pop can complete lines for both players at once, in which case the player who popped wins.
+pub fn play<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    mv: &Move<W>,
+    curr: &Token,
+) -> Option<Token> {
+    match mv {
+        Move::Drop(col) => {
+            let cell = board.place(col, curr).expect("the drop should be legal");
+            board.won_at(&cell).then_some(*curr)
+        }
+        Move::Pop(col) => {
+            debug_assert!(can_pop(board, col, curr), "the pop should be legal");
+            board.pop(col);
+            // every token in the column has moved, so any of them could complete a line
+            let winners: Vec<Token> = column_cells(board, col)
+                .filter_map(|cell| board.get(&cell).filter(|_| board.won_at(&cell)))
+                .collect();
+            if winners.contains(curr) {
+                Some(*curr)
+            } else {
+                winners.first().copied()
+            }
+        }
+    }
+}
+
+/// Undoes a move played by `curr` with `play`.
+pub fn undo<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    mv: &Move<W>,
+    curr: &Token,
+) {
+    match mv {
+        Move::Drop(col) => {
+            let top = column_cells(board, col)
+                .last()
+                .expect("the dropped token is on top of the column");
+            board.unplace(&top);
+        }
+        Move::Pop(col) => board.unpop(col, curr),
+    }
+}
+
+/// How a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win(Token),
+    Draw,
+}
+
+/// A game of PopOut. The game is drawn when a position occurs for the third time with the same
+/// player to move, or when the player to move has no legal move.
+#[derive(Clone, Debug)]
+pub struct Game<
+    B: MutBoard<W, H, K> + Clone + Hash,
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+> {
+    pub board: B,
+    pub curr: Token,
+    pub moves: Vec<Move<W>>,
+    pub outcome: Option<Outcome>,
+    /// The number of times each position has occurred, with the player to move.
+    seen: HashMap<(B, Token), usize>,
+}
+
+impl<B: MutBoard<W, H, K> + Clone + Hash, const W: usize, const H: usize, const K: usize>
+    Game<B, W, H, K>
+{
+    /// The number of times a position must occur for the game to be drawn.
+    pub const REPETITIONS: usize = 3;
+
+    pub fn new(board: B, curr: Token) -> Self {
+        let mut game = Game {
+            board,
+            curr,
+            moves: Vec::new(),
+            outcome: None,
+            seen: HashMap::new(),
+        };
+        game.arrive();
+        game
+    }
+
+    /// Plays the move for the player to move, returning the outcome if it ends the game.
+    pub fn play(&mut self, mv: Move<W>) -> Result<Option<Outcome>, String> {
+        if self.outcome.is_some() {
+            return Err("the game is over".to_owned());
+        }
+        if !moves(&self.board, &self.curr).contains(&mv) {
+            return Err(format!("`{mv}` is not a legal move"));
+        }
+
+        self.moves.push(mv);
+        if let Some(winner) = play(&mut self.board, &mv, &self.curr) {
+            self.outcome = Some(Outcome::Win(winner));
+        } else {
+            self.curr = self.curr.next();
+            self.arrive();
+        }
+        Ok(self.outcome)
+    }
+
+    /// Counts the current position, ending the game if it has been drawn.
+    fn arrive(&mut self) {
+        let count = self
+            .seen
+            .entry((self.board.clone(), self.curr))
+            .or_insert(0);
+        *count += 1;
+        if *count >= Self::REPETITIONS || moves(&self.board, &self.curr).is_empty() {
+            self.outcome = Some(Outcome::Draw);
+        }
+    }
+}
+
+/// Solves the position under PopOut rules to the given depth, as `minimax_cached` does for
+/// drops only.
+///
+/// `Game` only draws a position on its `Game::REPETITIONS`th occurrence, but the search draws
+/// a position as soon as it repeats on the line being searched. The results are the same:
+/// the player a repetition suits can play the same moves round again until the game is drawn,
+/// and the other player has the same choices each time round, so no better way out than the
+/// first time. A result that relied on a repetition depends on the line it was reached by,
+/// so only the other results are cached.
+pub fn solve<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K> + Clone + Hash,
+>(
+    board: B,
+    depth: usize,
+    curr: Token,
+) -> Option<Token> {
+    best_move(board, depth, curr).0
+}
+
+/// Solves the position as `solve` does, also returning a best move: the first that wins,
+/// else the first that does not lose, else the first legal move.
+pub fn best_move<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: MutBoard<W, H, K> + Clone + Hash,
+>(
+    mut board: B,
+    depth: usize,
+    curr: Token,
+) -> (Option<Token>, Option<Move<W>>) {
+    let mut path = HashSet::new();
+    let mut cache = HashMap::new();
+    path.insert((board.clone(), curr));
+
+    let moves = moves(&board, &curr);
+    let mut best = (Some(curr.next()), moves.first().copied());
+    if depth == 0 {
+        return (None, best.1);
+    }
+    if moves.is_empty() {
+        return (None, None);
+    }
+
+    for mv in moves {
+        let result = match play(&mut board, &mv, &curr) {
+            Some(winner) => Some(winner),
+            None => helper(&mut board, depth - 1, curr.next(), &mut path, &mut cache).0,
+        };
+        undo(&mut board, &mv, &curr);
+
+        match result {
+            Some(winner) if winner == curr => return (Some(curr), Some(mv)),
+            None if best.0.is_some() => best = (None, Some(mv)),
+            _ => (),
+        }
+    }
+    best
+}
+
+/// Returns the result, and whether it relied on a position repeating on the `path`.
+fn helper<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K> + Clone + Hash>(
+    board: &mut B,
+    depth: usize,
+    curr: Token,
+    path: &mut HashSet<(B, Token)>,
+    cache: &mut Cache<(B, Token)>,
+) -> (Option<Token>, bool) {
+    if depth == 0 {
+        return (None, false);
+    }
+    let key = (board.clone(), curr);
+    if let Some(cached_result) = cache.get(&key).and_then(|entry| entry.result_at(depth)) {
+        return (cached_result, false);
+    }
+    if !path.insert(key.clone()) {
+        return (None, true);
+    }
+
+    let moves = moves(board, &curr);
+    let mut out = if moves.is_empty() {
+        None
+    } else {
+        Some(curr.next())
+    };
+    let mut repeated = false;
+
+    for mv in moves {
+        let result = match play(board, &mv, &curr) {
+            Some(winner) => Some(winner),
+            None => {
+                let (result, repeats) = helper(board, depth - 1, curr.next(), path, cache);
+                repeated |= repeats;
+                result
+            }
+        };
+        undo(board, &mv, &curr);
+
+        match result {
+            Some(winner) if winner == curr => {
+                out = Some(curr);
+                break;
+            }
+            None => out = None,
+            _ => (),
+        }
+    }
+
+    path.remove(&key);
+    if !repeated {
+        cache.insert(key, CacheEntry { result: out, depth });
+    }
+    (out, repeated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::board::{array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard};
+    use crate::random::Rng;
+
+    #[test]
+    fn pops_agree_across_boards() {
+        let mut rng = Rng::seeded(0);
+        let mut array: ArrayBoard = ArrayBoard::EMPTY;
+        let mut bit: BitBoard = BitBoard::EMPTY;
+        let mut symm: SymmBoard = SymmBoard::EMPTY;
+        let mut curr = Token::START;
+
+        for _ in 0..500 {
+            let mv = *rng.choose(&moves(&array, &curr)).unwrap();
+            let winner = play(&mut array, &mv, &curr);
+            assert_eq!(play(&mut bit, &mv, &curr), winner);
+            assert_eq!(play(&mut symm, &mv, &curr), winner);
+            for col in column::IDXS {
+                for row in row::BOTTOM_UP {
+                    let cell = Cell { col, row };
+                    assert_eq!(bit.get(&cell), array.get(&cell));
+                    assert_eq!(symm.get(&cell), array.get(&cell));
+                }
+            }
+
+            // undoing and playing again gives the same board
+            let before = bit.clone();
+            undo(&mut bit, &mv, &curr);
+            play(&mut bit, &mv, &curr);
+            assert_eq!(bit, before);
+
+            if winner.is_some() {
+                array = ArrayBoard::EMPTY;
+                bit = BitBoard::EMPTY;
+                symm = SymmBoard::EMPTY;
+                curr = Token::START;
+            } else {
+                curr = curr.next();
+            }
+        }
+    }
+
+    // Yellow can pop the bottom of column 0 to drop their token in row 1 into line,
+    // which also lines up Red's tokens in row 0
+    const POP_WIN: &str = "|.......|
+                           |.......|
+                           |.......|
+                           |Y......|
+                           |RYYY...|
+                           |YRRR...|";
+
+    #[test]
+    fn popper_wins_double_lines() {
+        let mut board: BitBoard = BitBoard::read(POP_WIN);
+        let pop = Move::Pop(column::Idx::ZERO);
+        assert_eq!(play(&mut board, &pop, &Token::Yellow), Some(Token::Yellow));
+        let bottom = Cell {
+            col: column::Idx::ZERO,
+            row: row::Idx::BOTTOM,
+        };
+        assert_eq!(board.get(&bottom), Some(Token::Red));
+
+        undo(&mut board, &pop, &Token::Yellow);
+        assert_eq!(board, BitBoard::read(POP_WIN));
+    }
+
+    #[test]
+    fn pop_can_lose() {
+        // without Yellow's token at the end of row 1, popping only completes Red's line
+        let mut board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |Y......|
+             |RYYR...|
+             |YRRRY..|",
+        );
+        assert_eq!(
+            play(&mut board, &Move::Pop(column::Idx::ZERO), &Token::Yellow),
+            Some(Token::Red)
+        );
+        assert!(!can_pop(&board, &column::Idx::ZERO, &Token::Yellow));
+        assert!(can_pop(&board, &column::Idx::raw(4), &Token::Yellow));
+    }
+
+    #[test]
+    fn pops_move_blockers() {
+        // popping column 0 drops the blocker to the bottom and Red's token into row 1
+        const BLOCKED: &str = "|.....|
+                               |R....|
+                               |#RR..|
+                               |Y##YY|";
+        let mut board = BitBoard::<5, 3, 3>::read(BLOCKED);
+        let pop = Move::Pop(column::Idx::ZERO);
+        assert_eq!(play(&mut board, &pop, &Token::Yellow), Some(Token::Red));
+        undo(&mut board, &pop, &Token::Yellow);
+        assert_eq!(board, BitBoard::read(BLOCKED));
+
+        // a drop onto a blocker is undone from the top of the column
+        let mut board = BitBoard::<4, 4, 3>::read("|.#..|");
+        let drop = Move::Drop(column::Idx::raw(1));
+        assert_eq!(play(&mut board, &drop, &Token::Yellow), None);
+        undo(&mut board, &drop, &Token::Yellow);
+        assert_eq!(board, BitBoard::read("|.#..|"));
+
+        // no one has time to make three in a row
+        assert_eq!(solve(board, 4, Token::START), None);
+    }
+
+    #[test]
+    fn solver_finds_pops() {
+        let board: BitBoard = BitBoard::read(POP_WIN);
+        assert_eq!(
+            best_move(board.clone(), 1, Token::Yellow),
+            (Some(Token::Yellow), Some(Move::Pop(column::Idx::ZERO)))
+        );
+        // with drops only, there is no win
+        assert_eq!(minimax_cached(board.clone(), 1, Token::Yellow), None);
+        // but with Red to move, Red wins first by completing row 0
+        assert_eq!(solve(board, 1, Token::Red), Some(Token::Red));
+    }
+
+    #[test]
+    fn repetition_draws() {
+        let mut game = Game::new(BitBoard::<4, 4, 4>::EMPTY, Token::START);
+        let (first, second) = (column::Idx::raw(0), column::Idx::raw(1));
+        let cycle = [
+            Move::Drop(first),
+            Move::Drop(second),
+            Move::Pop(first),
+            Move::Pop(second),
+        ];
+        for mv in cycle {
+            assert_eq!(game.play(mv), Ok(None));
+        }
+        for mv in &cycle[..3] {
+            assert_eq!(game.play(*mv), Ok(None));
+        }
+        // back at the empty board with Yellow to move for the third time
+        assert_eq!(game.play(Move::Pop(second)), Ok(Some(Outcome::Draw)));
+        assert!(game.play(Move::Drop(first)).is_err());
+    }
+
+    #[test]
+    fn illegal_moves_are_rejected() {
+        let mut game = Game::new(BitBoard::<4, 4, 4>::EMPTY, Token::START);
+        let first = column::Idx::ZERO;
+        assert!(game.play(Move::Pop(first)).is_err());
+        game.play(Move::Drop(first)).unwrap();
+        // Red cannot pop Yellow's token
+        assert!(game.play(Move::Pop(first)).is_err());
+        assert_eq!(game.moves, [Move::Drop(first)]);
+    }
+
+    /// Solves as `Game` plays, drawing a position on its `Game::REPETITIONS`th occurrence,
+    /// without caching.
+    fn solve_by_game_rules<const W: usize, const H: usize, const K: usize>(
+        board: &mut BitBoard<W, H, K>,
+        depth: usize,
+        curr: Token,
+        seen: &mut HashMap<(BitBoard<W, H, K>, Token), usize>,
+    ) -> Option<Token> {
+        let key = (board.clone(), curr);
+        let count = seen.entry(key.clone()).or_insert(0);
+        *count += 1;
+        let moves = moves(board, &curr);
+        let mut out = if depth == 0
+            || *count >= Game::<BitBoard<W, H, K>, W, H, K>::REPETITIONS
+            || moves.is_empty()
+        {
+            None
+        } else {
+            Some(curr.next())
+        };
+
+        if out.is_some() {
+            for mv in moves {
+                let result = match play(board, &mv, &curr) {
+                    Some(winner) => Some(winner),
+                    None => solve_by_game_rules(board, depth - 1, curr.next(), seen),
+                };
+                undo(board, &mv, &curr);
+                match result {
+                    Some(winner) if winner == curr => {
+                        out = Some(curr);
+                        break;
+                    }
+                    None => out = None,
+                    _ => (),
+                }
+            }
+        }
+        *seen.get_mut(&key).unwrap() -= 1;
+        out
+    }
+
+    #[test]
+    fn path_repetitions_match_game_repetitions() {
+        // positions that both players can shuffle round by popping and dropping again
+        let mut rng = Rng::seeded(0);
+        for _ in 0..20 {
+            let mut board = BitBoard::<3, 3, 3>::EMPTY;
+            let mut curr = Token::START;
+            for _ in 0..rng.below(5) {
+                let mv = *rng.choose(&moves(&board, &curr)).unwrap();
+                if play(&mut board, &mv, &curr).is_some() {
+                    undo(&mut board, &mv, &curr);
+                    break;
+                }
+                curr = curr.next();
+            }
+
+            for depth in 1..=9 {
+                let expected = solve_by_game_rules(&mut board, depth, curr, &mut HashMap::new());
+                assert_eq!(solve(board.clone(), depth, curr), expected, "depth {depth}");
+            }
+        }
+    }
+
+    #[test]
+    fn solves_small_boards() {
+        // Yellow still wins connect three on 4x3 in 9 plies, with Red able to pop
+        let board = BitBoard::<4, 3, 3>::EMPTY;
+        assert_eq!(solve(board.clone(), 7, Token::START), None);
+        assert_eq!(solve(board, 9, Token::START), Some(Token::Yellow));
+    }
+}
@@ -0,0 +1,273 @@
+use crate::basic::*;
+use crate::board::Board;
+use crate::lines;
+
+/// Trait for rules that decide the result of a position without searching it,
+/// letting the solvers prune the whole subtree, or that extend the search
+/// through positions worth looking further into.
+pub trait PruningRule {
+    /// Returns `Some(winner)` (with `None` for a draw) if the rule decides the position
+    /// with `curr` to play and `depth` plies left to search, or None if it has to be searched.
+    /// A rule should only decide a win the search would find within `depth` plies.
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        curr: Token,
+        depth: usize,
+    ) -> Option<Option<Token>>;
+
+    /// Returns true if the move from this position, with `curr` to play,
+    /// should not count against the depth budget.
+    fn extends<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        _board: &B,
+        _curr: Token,
+    ) -> bool {
+        false
+    }
+}
+
+/// Combines two rules: decides with the first that decides, and extends if either extends.
+impl<A: PruningRule, C: PruningRule> PruningRule for (A, C) {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        curr: Token,
+        depth: usize,
+    ) -> Option<Option<Token>> {
+        self.0
+            .decide(board, curr, depth)
+            .or_else(|| self.1.decide(board, curr, depth))
+    }
+
+    fn extends<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        curr: Token,
+    ) -> bool {
+        self.0.extends(board, curr) || self.1.extends(board, curr)
+    }
+}
+
+/// Never prunes, the default for every solver.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoPruning;
+
+impl PruningRule for NoPruning {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        _board: &B,
+        _curr: Token,
+        _depth: usize,
+    ) -> Option<Option<Token>> {
+        None
+    }
+}
+
+/// Never prunes, but counts the positions the solver searches:
+/// those it reaches with depth left that are not answered by its cache.
+#[derive(Clone, Debug, Default)]
+pub struct NodeCounter {
+    nodes: std::cell::Cell<u64>,
+}
+
+impl NodeCounter {
+    pub fn nodes(&self) -> u64 {
+        self.nodes.get()
+    }
+
+    /// Counts one more node searched.
+    pub fn count(&self) {
+        self.nodes.set(self.nodes.get() + 1);
+    }
+}
+
+impl PruningRule for NodeCounter {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        _board: &B,
+        _curr: Token,
+        _depth: usize,
+    ) -> Option<Option<Token>> {
+        self.count();
+        None
+    }
+}
+
+/// Decides positions where neither player can complete a line as draws.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeadPosition;
+
+impl PruningRule for DeadPosition {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        _curr: Token,
+        _depth: usize,
+    ) -> Option<Option<Token>> {
+        lines::is_dead(board).then_some(None)
+    }
+}
+
+/// Extends the search through forced moves: positions where the player to move
+/// has at most one move that does not lose immediately, so a forced sequence of
+/// blocks is not cut off just before the decisive move.
+/// Never extends under misère rules, where a line is forced on the player who completes it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ForcedMoves;
+
+impl PruningRule for ForcedMoves {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        _board: &B,
+        _curr: Token,
+        _depth: usize,
+    ) -> Option<Option<Token>> {
+        None
+    }
+
+    fn extends<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        curr: Token,
+    ) -> bool {
+        if B::MISERE {
+            return false;
+        }
+
+        let cells: Vec<Cell<W, H>> = column::Idx::<W>::ALL
+            .into_iter()
+            .filter_map(|col| board.next_cell(&col))
+            .collect();
+
+        // an immediate win ends the search anyway
+        if cells.iter().any(|cell| board.would_win_at(cell, &curr)) {
+            return false;
+        }
+
+        let blocks: Vec<&Cell<W, H>> = cells
+            .iter()
+            .filter(|cell| board.would_win_at(cell, &curr.next()))
+            .collect();
+        let candidates = match blocks[..] {
+            [] => cells.iter().collect(),
+            [block] => vec![block],
+            _ => return true,
+        };
+
+        // a move loses if it lets the opponent win directly above it
+        let non_losing = candidates.into_iter().filter(|cell| {
+            cell.try_shift((0, 1))
+                .is_none_or(|above| !board.would_win_at(&above, &curr.next()))
+        });
+        non_losing.take(2).count() <= 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_basic::minimax_mut_with;
+    use crate::algorithms::minimax_cached::minimax_cached_with;
+    use crate::board::{
+        array_board::ArrayBoard, bit_board::BitBoard, misere::Misere, symm_board::SymmBoard,
+    };
+    use crate::test_positions::{EASY_2, MEDIUM_2};
+
+    make_easy_tests!(
+        |mut b, d| {
+            let curr = b.curr_player();
+            minimax_mut_with(&mut b, d, curr, &DeadPosition)
+        },
+        ArrayBoard,
+        BitBoard,
+        SymmBoard
+    );
+
+    make_medium_tests!(
+        |mut b, d| {
+            let curr = b.curr_player();
+            minimax_cached_with(b, d, curr, &DeadPosition)
+        },
+        BitBoard
+    );
+
+    mod forced_moves {
+        use super::*;
+
+        make_easy_tests!(
+            |mut b, d| {
+                let curr = b.curr_player();
+                minimax_mut_with(&mut b, d, curr, &ForcedMoves)
+            },
+            ArrayBoard,
+            BitBoard,
+            SymmBoard
+        );
+    }
+
+    #[test]
+    fn dead_position_is_draw() {
+        let board: BitBoard = BitBoard::read(
+            "|RR.YRR.|
+             |YYRRYYR|
+             |RRYYRRY|
+             |YYRRYYR|
+             |YYRRYYR|
+             |YYRRYYR|",
+        );
+        assert_eq!(DeadPosition.decide(&board, Token::Yellow, 1), Some(None));
+        assert_eq!(NoPruning.decide(&board, Token::Yellow, 1), None);
+    }
+
+    #[test]
+    fn rules_hold_under_misere() {
+        let board = Misere(BitBoard::<4, 3, 3>::EMPTY);
+        let (depth, red) = (12, Some(Token::Red));
+        assert_eq!(
+            minimax_cached_with(board.clone(), depth, Token::START, &DeadPosition),
+            red
+        );
+        assert_eq!(
+            minimax_cached_with(board.clone(), depth, Token::START, &ForcedMoves),
+            red
+        );
+    }
+
+    #[test]
+    fn forced_moves_extend_depth() {
+        for (position, depth) in [(EASY_2, 2), (MEDIUM_2, 12)] {
+            let board: BitBoard = BitBoard::read(position.board);
+            let curr = board.curr_player();
+            assert_eq!(
+                minimax_cached_with(board.clone(), depth, curr, &NoPruning),
+                None
+            );
+            assert_eq!(
+                minimax_cached_with(board.clone(), depth, curr, &ForcedMoves),
+                position.winner
+            );
+            assert_eq!(
+                minimax_cached_with(board, depth, curr, &(DeadPosition, ForcedMoves)),
+                position.winner
+            );
+        }
+    }
+
+    #[test]
+    fn forced_moves_only_when_one_move_holds() {
+        // Yellow must block at (0, 0)
+        let board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |....Y..|
+             |.RRRYY.|",
+        );
+        assert!(ForcedMoves.extends(&board, Token::Yellow));
+        assert!(!ForcedMoves.extends(&<BitBoard>::EMPTY, Token::Yellow));
+        // under misère rules Yellow is glad to leave the line to Red
+        assert!(!ForcedMoves.extends(&Misere(board), Token::Yellow));
+    }
+}
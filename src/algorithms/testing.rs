@@ -2,8 +2,6 @@ use crate::basic::{Cell, Token, column, row};
 use crate::board::{Board, CloneBoard, MutBoard};
 use crate::test_positions::Position;
 
-use paste;
-
 macro_rules! make_test_with_board_on_position {
     ($func:expr, $b:ty, $pos:ident) => {
         paste::paste! {
@@ -0,0 +1,326 @@
+use crate::basic::*;
+use crate::board::{GameStatus, MutBoard};
+use std::fmt::{Display, Formatter};
+
+/// A forced win: the key move, and the refutation of every defence to it.
+/// `replies` is empty when the key move wins outright, and a defence has no refutation
+/// when it loses outright, completing a line under misère rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WinTree<const W: usize = { column::COUNT }> {
+    pub key: column::Idx<W>,
+    pub replies: Vec<(column::Idx<W>, Option<WinTree<W>>)>,
+}
+
+impl<const W: usize> WinTree<W> {
+    /// Returns the number of plies until the win, against the longest defence.
+    pub fn plies(&self) -> usize {
+        1 + self
+            .replies
+            .iter()
+            .map(|(_, tree)| 1 + tree.as_ref().map_or(0, WinTree::plies))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the principal line: the key moves along the longest defences.
+    pub fn main_line(&self) -> Vec<column::Idx<W>> {
+        let mut line = vec![self.key];
+        if let Some((reply, tree)) = self
+            .replies
+            .iter()
+            .max_by_key(|(_, tree)| tree.as_ref().map_or(0, WinTree::plies))
+        {
+            line.push(*reply);
+            line.extend(tree.iter().flat_map(WinTree::main_line));
+        }
+        line
+    }
+
+    fn fmt_indented(&self, f: &mut Formatter<'_>, indent: usize) -> std::fmt::Result {
+        writeln!(f, "{:indent$}{}", "", self.key)?;
+        for (reply, tree) in &self.replies {
+            match tree {
+                Some(tree) => {
+                    writeln!(f, "{:indent$}  {reply}:", "")?;
+                    tree.fmt_indented(f, indent + 4)?;
+                }
+                None => writeln!(f, "{:indent$}  {reply}: loses", "")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints the key move, then each defence followed by its refutation, indented.
+impl<const W: usize> Display for WinTree<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+/// The shortest forced win for the player to move.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WinInN<const W: usize = { column::COUNT }> {
+    /// The number of plies until the win, counting the move that decides it.
+    pub plies: usize,
+    /// Every move that forces the win in `plies`.
+    pub keys: Vec<column::Idx<W>>,
+    /// The full winning line tree from the first key move.
+    pub tree: WinTree<W>,
+    /// The number of positions searched to find the shortest win and its key moves.
+    pub nodes: usize,
+}
+
+impl<const W: usize> WinInN<W> {
+    /// Returns true if only one move forces the win in `plies`.
+    pub fn is_unique(&self) -> bool {
+        self.keys.len() == 1
+    }
+}
+
+/// Finds the shortest forced win for `curr` within `max_plies` plies, or None if there isn't one.
+pub fn win_in_n<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    max_plies: usize,
+    curr: Token,
+) -> Option<WinInN<W>> {
+    let mut nodes = 0;
+    let plies = shortest_win(board, max_plies, curr, &mut nodes)?;
+    let keys: Vec<column::Idx<W>> = column::Idx::<W>::CENTRED_FIRST
+        .into_iter()
+        .filter(|col| move_wins_within(board, col, plies, curr, &mut nodes))
+        .collect();
+    let tree = win_tree(board, &keys[0], plies, curr);
+    Some(WinInN {
+        plies,
+        keys,
+        tree,
+        nodes,
+    })
+}
+
+/// Returns the fewest plies within `max_plies` in which `curr` can force a win.
+fn shortest_win<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    max_plies: usize,
+    curr: Token,
+    nodes: &mut usize,
+) -> Option<usize> {
+    // the move deciding the game is ours, so wins take an odd number of plies,
+    // or under misère rules the opponent's, and an even number
+    let first = if B::MISERE { 2 } else { 1 };
+    (first..=max_plies)
+        .step_by(2)
+        .find(|&plies| wins_within(board, plies, curr, nodes))
+}
+
+/// Returns true if `curr` can force a win within `plies` plies.
+fn wins_within<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    plies: usize,
+    curr: Token,
+    nodes: &mut usize,
+) -> bool {
+    column::Idx::<W>::CENTRED_FIRST
+        .iter()
+        .any(|col| move_wins_within(board, col, plies, curr, nodes))
+}
+
+/// Returns true if playing `col` lets `curr` force a win within `plies` plies.
+fn move_wins_within<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    col: &column::Idx<W>,
+    plies: usize,
+    curr: Token,
+    nodes: &mut usize,
+) -> bool {
+    if plies == 0 {
+        return false;
+    }
+    let Some(cell) = board.place(col, &curr) else {
+        return false;
+    };
+    *nodes += 1;
+
+    let wins = match board.status(&cell, curr) {
+        GameStatus::Won(winner) => winner == curr,
+        GameStatus::Drawn => false,
+        GameStatus::Ongoing => plies >= 2 && defences_fail(board, plies - 1, curr, nodes),
+    };
+    board.unplace(&cell);
+    wins
+}
+
+/// Returns true if every defence by the opponent of `curr` still loses within `plies` plies,
+/// counting the defence.
+fn defences_fail<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    plies: usize,
+    curr: Token,
+    nodes: &mut usize,
+) -> bool {
+    column::Idx::<W>::CENTRED_FIRST.iter().all(|col| {
+        let Some(cell) = board.place(col, &curr.next()) else {
+            return true;
+        };
+        *nodes += 1;
+        let fails = match board.status(&cell, curr.next()) {
+            GameStatus::Won(winner) => winner == curr,
+            GameStatus::Drawn => false,
+            GameStatus::Ongoing => plies >= 2 && wins_within(board, plies - 1, curr, nodes),
+        };
+        board.unplace(&cell);
+        fails
+    })
+}
+
+/// Builds the tree of a key move known to win within `plies`,
+/// refuting each defence with the shortest win left.
+fn win_tree<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+    board: &mut B,
+    key: &column::Idx<W>,
+    plies: usize,
+    curr: Token,
+) -> WinTree<W> {
+    let cell = board.place(key, &curr).expect("the key move is playable");
+    let mut replies = Vec::new();
+    let mut nodes = 0;
+
+    if board.status(&cell, curr) == GameStatus::Ongoing {
+        for col in column::Idx::<W>::ALL {
+            let Some(reply) = board.place(&col, &curr.next()) else {
+                continue;
+            };
+            // a defence ending the game can only lose, completing a line under misère rules
+            let tree = (board.status(&reply, curr.next()) == GameStatus::Ongoing).then(|| {
+                let left =
+                    shortest_win(board, plies - 2, curr, &mut nodes).expect("every defence loses");
+                let next_key = column::Idx::<W>::CENTRED_FIRST
+                    .into_iter()
+                    .find(|next| move_wins_within(board, next, left, curr, &mut nodes))
+                    .expect("a winning move exists");
+                win_tree(board, &next_key, left, curr)
+            });
+            replies.push((col, tree));
+            board.unplace(&reply);
+        }
+    }
+
+    board.unplace(&cell);
+    WinTree { key: *key, replies }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::board::{Board, bit_board::BitBoard, misere::Misere};
+    use crate::test_positions::EASY_2;
+
+    /// Plays out every line of the tree, checking the attacker wins each one.
+    fn check_tree<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>(
+        board: &mut B,
+        tree: &WinTree<W>,
+        curr: Token,
+    ) {
+        let cell = board.place(&tree.key, &curr).unwrap();
+        if tree.replies.is_empty() {
+            assert_eq!(
+                board.status(&cell, curr),
+                GameStatus::Won(curr),
+                "line ends without a win"
+            );
+        } else {
+            let replies = column::Idx::<W>::ALL
+                .into_iter()
+                .filter(|col| board.can_place(col))
+                .count();
+            assert_eq!(tree.replies.len(), replies, "a defence is missing");
+            for (reply, next) in &tree.replies {
+                let reply = board.place(reply, &curr.next()).unwrap();
+                match next {
+                    Some(next) => {
+                        let status = board.status(&reply, curr.next());
+                        assert_eq!(status, GameStatus::Ongoing, "a defence ends the game");
+                        check_tree(board, next, curr);
+                    }
+                    None => assert_eq!(
+                        board.status(&reply, curr.next()),
+                        GameStatus::Won(curr),
+                        "a defence does not lose"
+                    ),
+                }
+                board.unplace(&reply);
+            }
+        }
+        board.unplace(&cell);
+    }
+
+    #[test]
+    fn immediate_win() {
+        let mut board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |.RRR...|
+             |.YYY...|",
+        );
+        let win = win_in_n(&mut board, 5, Token::Yellow).unwrap();
+        assert_eq!(win.plies, 1);
+        assert_eq!(win.keys, [column::Idx::raw(4), column::Idx::raw(0)]);
+        assert!(!win.is_unique());
+        assert_eq!(win.tree.plies(), 1);
+        assert!(win.nodes > 0);
+    }
+
+    #[test]
+    fn unique_key_move() {
+        let mut board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |.RR....|
+             |.YY....|",
+        );
+        let win = win_in_n(&mut board, 7, Token::Yellow).unwrap();
+        assert_eq!(win.plies, 3);
+        assert!(win.is_unique());
+        assert_eq!(win.tree.key, column::Idx::raw(3));
+        check_tree(&mut board, &win.tree, Token::Yellow);
+    }
+
+    #[test]
+    fn tactical_position() {
+        let mut board: BitBoard = BitBoard::read(EASY_2.board);
+        let curr = board.curr_player();
+        assert_eq!(Some(curr), EASY_2.winner);
+
+        let win = win_in_n(&mut board, EASY_2.depth, curr).unwrap();
+        assert_eq!(win.tree.plies(), win.plies);
+        assert_eq!(win.tree.main_line().len(), win.plies);
+        check_tree(&mut board, &win.tree, curr);
+
+        // one ply less is not enough
+        assert_eq!(minimax_cached(board.clone(), win.plies - 1, curr), None);
+        assert!(win_in_n(&mut board, win.plies - 1, curr).is_none());
+    }
+
+    #[test]
+    fn no_win_on_empty_board() {
+        let mut board: BitBoard = BitBoard::EMPTY;
+        assert!(win_in_n(&mut board, 7, Token::START).is_none());
+    }
+
+    #[test]
+    fn misere_wins_take_the_opponents_line() {
+        // Yellow wins misère 3x3 connect three, every win ending with Red completing a line
+        let mut board = Misere(BitBoard::<3, 3, 3>::EMPTY);
+        let win = win_in_n(&mut board, 9, Token::Yellow).unwrap();
+        assert_eq!(win.plies % 2, 0);
+        assert_eq!(win.tree.plies(), win.plies);
+        check_tree(&mut board, &win.tree, Token::Yellow);
+        assert!(win_in_n(&mut board, win.plies - 1, Token::Yellow).is_none());
+    }
+}
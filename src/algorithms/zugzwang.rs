@@ -0,0 +1,428 @@
+use crate::algorithms::evaluators::{Evaluator, ThreatParity, threats};
+use crate::algorithms::pruning::PruningRule;
+use crate::basic::*;
+use crate::board::Board;
+
+/// The parity of a row, counting the bottom row as row 1 (odd).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Parity {
+    Odd,
+    Even,
+}
+
+impl Parity {
+    pub fn of<const H: usize>(row: row::Idx<H>) -> Parity {
+        if usize::from(row) % 2 == 0 {
+            Parity::Odd
+        } else {
+            Parity::Even
+        }
+    }
+
+    /// The parity of the threats that favour the given player:
+    /// odd for the first player, even for the second.
+    pub fn favouring(player: Token) -> Parity {
+        if player == Token::START {
+            Parity::Odd
+        } else {
+            Parity::Even
+        }
+    }
+}
+
+/// An empty cell that would complete a line for `player`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Threat<const W: usize = { column::COUNT }, const H: usize = { row::COUNT }> {
+    pub cell: Cell<W, H>,
+    pub player: Token,
+}
+
+impl<const W: usize, const H: usize> Threat<W, H> {
+    pub fn parity(&self) -> Parity {
+        Parity::of(self.cell.row)
+    }
+
+    /// Returns true if the threat is on a row of the parity that favours its player.
+    pub fn is_favourable(&self) -> bool {
+        self.parity() == Parity::favouring(self.player)
+    }
+}
+
+/// The odd/even threat analysis of a position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis<const W: usize = { column::COUNT }, const H: usize = { row::COUNT }> {
+    pub threats: Vec<Threat<W, H>>,
+    /// The number of empty cells that can be played without moving directly below a threat.
+    pub free: usize,
+    /// The player who is not forced to move below a threat once the free cells run out.
+    pub controller: Token,
+    /// The predicted result once the board fills up, None for a draw.
+    pub outcome: Option<Token>,
+}
+
+impl<const W: usize, const H: usize> Analysis<W, H> {
+    /// Returns the given player's threats of the given parity.
+    pub fn threats_of(&self, player: Token, parity: Parity) -> impl Iterator<Item = &Threat<W, H>> {
+        self.threats
+            .iter()
+            .filter(move |threat| threat.player == player && threat.parity() == parity)
+    }
+
+    /// Returns true if the position is filled up: every empty cell is directly below
+    /// a threat or above one, so the outcome follows from zugzwang alone.
+    pub fn is_filled_up(&self) -> bool {
+        self.free == 0
+    }
+}
+
+/// A column reduced to its height and the rows of its threats, lowest first.
+/// A threat owner of None means both players threaten the cell.
+#[derive(Clone, Debug)]
+struct Column {
+    height: usize,
+    threats: Vec<(usize, Option<Token>)>,
+}
+
+impl Column {
+    fn lowest(&self) -> Option<(usize, Option<Token>)> {
+        self.threats
+            .iter()
+            .copied()
+            .find(|&(row, _)| row >= self.height)
+    }
+}
+
+/// Returns true if the threat owner includes the given player.
+fn owns(owner: Option<Token>, player: Token) -> bool {
+    owner.is_none_or(|owner| owner == player)
+}
+
+/// Counts the cells that can be played without moving directly below a threat,
+/// in columns `H` cells high.
+fn free_cells<const H: usize>(cols: &[Column]) -> usize {
+    cols.iter()
+        .map(|col| match col.lowest() {
+            Some((row, _)) => row.saturating_sub(col.height + 1),
+            None => H - col.height,
+        })
+        .sum()
+}
+
+/// Plays out the position assuming both players fill the free cells first,
+/// and then whoever runs out of moves must play below a threat.
+/// A player forced to do so uses their own threats first, which the opponent blocks.
+fn play_out<const H: usize>(cols: &[Column], curr: Token) -> Option<Token> {
+    let immediate = |player: Token| -> Vec<usize> {
+        (0..cols.len())
+            .filter(|&i| {
+                cols[i]
+                    .lowest()
+                    .is_some_and(|(row, owner)| row == cols[i].height && owns(owner, player))
+            })
+            .collect()
+    };
+
+    if !immediate(curr).is_empty() {
+        return Some(curr);
+    }
+    let blocks = immediate(curr.next());
+    match blocks[..] {
+        [] => (),
+        [col] => {
+            let mut cols = cols.to_vec();
+            cols[col].height += 1;
+            return play_out::<H>(&cols, curr.next());
+        }
+        _ => return Some(curr.next()),
+    }
+
+    let forced = if free_cells::<H>(cols).is_multiple_of(2) {
+        curr
+    } else {
+        curr.next()
+    };
+
+    let mut filled = cols.to_vec();
+    for col in filled.iter_mut() {
+        col.height = match col.lowest() {
+            Some((row, _)) => row - 1,
+            None => H,
+        };
+    }
+
+    let mut best = None;
+    let mut any_threats = false;
+    for i in 0..filled.len() {
+        let Some((row, owner)) = filled[i].lowest() else {
+            continue;
+        };
+        any_threats = true;
+        if owner != Some(forced) {
+            continue;
+        }
+
+        // play below our own threat, and the opponent blocks it
+        let mut next = filled.clone();
+        next[i].height = row + 1;
+        let result = play_out::<H>(&next, forced);
+        if result == Some(forced) {
+            return result;
+        }
+        if best.is_none() || result.is_none() {
+            best = Some(result);
+        }
+    }
+
+    match best {
+        Some(result) => result,
+        None if any_threats => Some(forced.next()),
+        None => None,
+    }
+}
+
+/// Returns the threats of both players.
+fn all_threats<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+) -> Vec<Threat<W, H>> {
+    let mut all = Vec::new();
+    for player in [Token::Yellow, Token::Red] {
+        all.extend(
+            threats(board, player)
+                .into_iter()
+                .map(|cell| Threat { cell, player }),
+        );
+    }
+    all
+}
+
+fn columns<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    threats: &[Threat<W, H>],
+) -> Vec<Column> {
+    let mut cols: Vec<Column> = column::Idx::<W>::ALL
+        .into_iter()
+        .map(|col| Column {
+            height: row::Idx::<H>::BOTTOM_UP
+                .take_while(|&row| board.piece(&Cell { col, row }).is_some())
+                .count(),
+            threats: Vec::new(),
+        })
+        .collect();
+
+    for threat in threats {
+        let col = &mut cols[usize::from(threat.cell.col)];
+        let row = usize::from(threat.cell.row);
+        match col.threats.iter_mut().find(|(r, _)| *r == row) {
+            // both players threaten this cell
+            Some(existing) => existing.1 = None,
+            None => col.threats.push((row, Some(threat.player))),
+        }
+    }
+    for col in cols.iter_mut() {
+        col.threats.sort_by_key(|&(row, _)| row);
+    }
+    cols
+}
+
+/// Analyses the threats of both players, with `curr` to play, to predict
+/// who controls zugzwang and who wins once the board fills up.
+/// The prediction ignores any new threats made while filling up or blocking.
+pub fn analyse<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    curr: Token,
+) -> Analysis<W, H> {
+    let all = all_threats(board);
+    let cols = columns(board, &all);
+    let free = free_cells::<H>(&cols);
+    Analysis {
+        threats: all,
+        free,
+        controller: if free.is_multiple_of(2) {
+            curr.next()
+        } else {
+            curr
+        },
+        outcome: play_out::<H>(&cols, curr),
+    }
+}
+
+/// Scores positions by their predicted zugzwang outcome with `player` to move,
+/// as `negamax` evaluates them, breaking ties with `ThreatParity`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Zugzwang;
+
+impl Zugzwang {
+    /// Score of a predicted win, kept well below `negamax::WIN_SCORE`.
+    const OUTCOME_SCORE: isize = 1000;
+}
+
+impl Evaluator for Zugzwang {
+    fn evaluate<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        player: Token,
+    ) -> isize {
+        // the predicted outcome assumes completing a line wins, so says nothing under misère rules
+        let outcome = match analyse(board, player).outcome {
+            _ if B::MISERE => 0,
+            Some(winner) if winner == player => Self::OUTCOME_SCORE,
+            Some(_) => -Self::OUTCOME_SCORE,
+            None => 0,
+        };
+        outcome + ThreatParity.evaluate(board, player)
+    }
+}
+
+/// Decides filled-up positions where the player to move is in zugzwang:
+/// every playable cell is directly below an opponent's threat, so any move loses.
+/// Only decides exact results, and only a loss with the two plies left to play it out,
+/// so it is safe to use in the solvers. Never decides under misère rules.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZugzwangRule;
+
+impl PruningRule for ZugzwangRule {
+    fn decide<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+        &self,
+        board: &B,
+        curr: Token,
+        depth: usize,
+    ) -> Option<Option<Token>> {
+        if B::MISERE {
+            return None;
+        }
+
+        let cols = columns(board, &all_threats(board));
+        if free_cells::<H>(&cols) != 0 {
+            return None;
+        }
+
+        let mut any_threats = false;
+        for col in &cols {
+            let Some((row, owner)) = col.lowest() else {
+                continue;
+            };
+            // immediate threats and our own threats are left to the search
+            if row == col.height || owner == Some(curr) {
+                return None;
+            }
+            any_threats = true;
+        }
+
+        // with no threats and no free cells, the board is full,
+        // otherwise the loss takes our move and the opponent's win
+        match any_threats {
+            false => Some(None),
+            true if depth >= 2 => Some(Some(curr.next())),
+            true => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::{minimax_cached, minimax_cached_with};
+    use crate::board::{
+        Board, array_board::ArrayBoard, bit_board::BitBoard, symm_board::SymmBoard,
+    };
+
+    make_easy_tests!(
+        |mut b, d| {
+            let curr = b.curr_player();
+            minimax_cached_with(b, d, curr, &ZugzwangRule)
+        },
+        ArrayBoard,
+        BitBoard,
+        SymmBoard
+    );
+
+    make_medium_tests!(
+        |mut b, d| {
+            let curr = b.curr_player();
+            minimax_cached_with(b, d, curr, &ZugzwangRule)
+        },
+        BitBoard
+    );
+
+    #[test]
+    fn zugzwang_needs_depth_to_lose() {
+        // Red can only play below Yellow's threat at (3, 4)
+        let board: BitBoard = BitBoard::read(
+            "|RRR.YYY|
+             |YYY.YRY|
+             |RYR.RYR|
+             |YRRYRRY|
+             |RYYRYYR|
+             |YYRYRRR|",
+        );
+        assert_eq!(
+            ZugzwangRule.decide(&board, Token::Red, 2),
+            Some(Some(Token::Yellow))
+        );
+        assert_eq!(ZugzwangRule.decide(&board, Token::Red, 1), None);
+        for depth in 1..=2 {
+            assert_eq!(
+                minimax_cached_with(board.clone(), depth, Token::Red, &ZugzwangRule),
+                minimax_cached(board.clone(), depth, Token::Red)
+            );
+        }
+    }
+
+    #[test]
+    fn evaluates_for_the_given_player() {
+        // the only move left is col 3, which loses for whoever has to play it
+        let board: BitBoard = BitBoard::read(
+            "|RRR.YYY|
+             |YYY.YRY|
+             |RYR.RYR|
+             |YRRYRRY|
+             |RYYRYYR|
+             |YYRYRRR|",
+        );
+        assert_eq!(analyse(&board, Token::Yellow).outcome, Some(Token::Red));
+        assert!(Zugzwang.evaluate(&board, Token::Red) < 0);
+        assert!(Zugzwang.evaluate(&board, Token::Yellow) < 0);
+    }
+
+    #[test]
+    fn empty_board_favours_second_player() {
+        let analysis = analyse(&<BitBoard>::EMPTY, Token::START);
+        assert!(analysis.threats.is_empty());
+        assert_eq!(analysis.controller, Token::START.next());
+        assert_eq!(analysis.outcome, None);
+    }
+
+    #[test]
+    fn odd_threat_wins_for_first_player() {
+        // Yellow threatens (3, 2), an odd row
+        let board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |YYY....|
+             |YRR...R|
+             |RYYR..R|",
+        );
+        let analysis = analyse(&board, Token::Yellow);
+        let odd: Vec<_> = analysis.threats_of(Token::Yellow, Parity::Odd).collect();
+        assert_eq!(odd.len(), 1);
+        assert!(odd[0].is_favourable());
+        assert_eq!(analysis.outcome, Some(Token::Yellow));
+    }
+
+    #[test]
+    fn even_threat_wins_for_second_player() {
+        // Red threatens (3, 1), an even row
+        let board: BitBoard = BitBoard::read(
+            "|.......|
+             |.......|
+             |.......|
+             |.......|
+             |RRR...Y|
+             |YYR...Y|",
+        );
+        let analysis = analyse(&board, Token::Yellow);
+        assert_eq!(analysis.threats_of(Token::Red, Parity::Even).count(), 1);
+        assert_eq!(analysis.outcome, Some(Token::Red));
+    }
+}
@@ -6,7 +6,7 @@ use std::ops::RangeInclusive;
 
 /// A Token in the game, either Yellow or Red.
 /// (Yellow starts)
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
 pub enum Token {
     Yellow,
     Red,
@@ -34,34 +34,56 @@ impl Display for Token {
     }
 }
 
+/// What fills a cell: a player's token, or a neutral blocker belonging to neither player,
+/// set up before the game starts for tokens to stack on.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
+pub enum Piece {
+    Token(Token),
+    Blocker,
+}
+
+/// The character for a blocker in `Board::read` and `Board::to_string`.
+pub const BLOCKER: char = '#';
+
+/// The number of same-colour tokens in a line needed to win on the standard board.
+pub const CONNECT: usize = 4;
+
 pub mod column {
 
+    /// The number of columns on the standard board.
     pub const COUNT: usize = 7;
 
-    pub type Idx = super::FiniteIndex<6>;
-
-    pub const IDXS: [Idx; COUNT] = [
-        Idx::raw(0),
-        Idx::raw(1),
-        Idx::raw(2),
-        Idx::raw(3),
-        Idx::raw(4),
-        Idx::raw(5),
-        Idx::raw(6),
-    ];
-
-    pub const IDXS_CENTRED_FIRST: [Idx; COUNT] = [
-        Idx::raw(3),
-        Idx::raw(2),
-        Idx::raw(4),
-        Idx::raw(1),
-        Idx::raw(5),
-        Idx::raw(0),
-        Idx::raw(6),
-    ];
-
-    impl Idx {
-        pub const CENTRE: Self = Idx::raw(3);
+    /// A column of a board `W` columns wide.
+    pub type Idx<const W: usize = COUNT> = super::FiniteIndex<W>;
+
+    pub const IDXS: [Idx; COUNT] = Idx::ALL;
+
+    pub const IDXS_CENTRED_FIRST: [Idx; COUNT] = Idx::CENTRED_FIRST;
+
+    impl<const W: usize> Idx<W> {
+        /// The centre column, or the right of the two central columns on an even width.
+        pub const CENTRE: Self = Self::raw(W / 2);
+
+        /// Every column from the centre outwards, taking the left of two equally central
+        /// columns first.
+        pub const CENTRED_FIRST: [Self; W] = {
+            let mut cols = [Self::ZERO; W];
+            let (mut i, mut distance) = (0, 0);
+            while i < W {
+                // twice the distance from the centre, so that it is whole on even widths
+                let mut col = 0;
+                while col < W {
+                    if (2 * col).abs_diff(W - 1) == distance {
+                        cols[i] = Self::raw(col);
+                        i += 1;
+                    }
+                    col += 1;
+                }
+                distance += 1;
+            }
+            cols
+        };
+
         /// Returns the column on the opposite side of the board, based on symmetry.
         pub fn flipped(&self) -> Self {
             Self::raw(usize::from(Self::MAX) - usize::from(*self))
@@ -72,50 +94,66 @@ pub mod column {
 pub mod row {
     use super::*;
 
+    /// The number of rows on the standard board.
     pub const COUNT: usize = 6;
 
-    pub type Idx = super::FiniteIndex<5>;
+    /// A row of a board `H` rows high.
+    pub type Idx<const H: usize = COUNT> = super::FiniteIndex<H>;
 
-    impl Idx {
+    impl<const H: usize> Idx<H> {
         pub const BOTTOM: Self = Self::ZERO;
         pub const TOP: Self = Self::MAX;
+        /// bottom to top
+        pub const BOTTOM_UP: RangeInclusive<Self> = Self::ZERO..=Self::MAX;
     }
     /// bottom to top
-    pub const BOTTOM_UP: RangeInclusive<Idx> = Idx::ZERO..=Idx::MAX;
+    pub const BOTTOM_UP: RangeInclusive<Idx> = Idx::BOTTOM_UP;
 }
 
-/// A Cell on the board, defined by a column and row index.
+/// A Cell on a board `W` columns wide and `H` rows high, defined by a column and row index.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
-pub struct Cell {
-    pub col: column::Idx,
-    pub row: row::Idx,
+pub struct Cell<const W: usize = { column::COUNT }, const H: usize = { row::COUNT }> {
+    pub col: column::Idx<W>,
+    pub row: row::Idx<H>,
 }
 
-impl Cell {
+impl<const W: usize, const H: usize> Cell<W, H> {
     /// Tries to shift by (col, row)
-    pub fn try_shift(&self, by: (isize, isize)) -> Option<Cell> {
+    pub fn try_shift(&self, by: (isize, isize)) -> Option<Self> {
         let col = self.col.try_shift(by.0)?;
         let row = self.row.try_shift(by.1)?;
         Some(Cell { col, row })
     }
 
+    /// Tries to shift by (col, row) as `try_shift` does, but wraps round from the last column
+    /// to the first and back, as on a cylinder. Returns None when off the top or bottom.
+    pub fn try_shift_around(&self, by: (isize, isize)) -> Option<Self> {
+        let col = (usize::from(self.col) as isize + by.0).rem_euclid(W as isize);
+        let col = column::Idx::raw(col as usize);
+        let row = self.row.try_shift(by.1)?;
+        Some(Cell { col, row })
+    }
+
     /// Returns an iterator over the cells in the same row as this cell,
-    /// from 3 columns to the left to 3 columns to the right (capped at the board edges).
-    pub fn row_neighbourhood(&self) -> impl Iterator<Item = Cell> {
-        (self.col.shift(-3)..=self.col.shift(3)).map(move |col| Cell { col, row: self.row })
+    /// that could be in a line of `connect` with it (capped at the board edges).
+    pub fn row_neighbourhood(&self, connect: usize) -> impl Iterator<Item = Self> {
+        let reach = connect as isize - 1;
+        (self.col.shift(-reach)..=self.col.shift(reach)).map(move |col| Cell { col, row: self.row })
     }
 
     /// Returns an iterator over the cells in the same column as this cell,
-    /// from 3 rows below to 3 rows above (capped at the board edges).
-    pub fn col_neighbourhood(&self) -> impl Iterator<Item = Cell> {
-        (self.row.shift(-3)..=self.row.shift(3)).map(move |row| Cell { col: self.col, row })
+    /// that could be in a line of `connect` with it (capped at the board edges).
+    pub fn col_neighbourhood(&self, connect: usize) -> impl Iterator<Item = Self> {
+        let reach = connect as isize - 1;
+        (self.row.shift(-reach)..=self.row.shift(reach)).map(move |row| Cell { col: self.col, row })
     }
 
     /// Returns an iterator over the cells in the same diagonal (bottom-left to top-right)
-    /// as this cell within a distance of 3 (capped at the board edges).
-    pub fn diag1_neighbourhood(&self) -> impl Iterator<Item = Cell> {
+    /// as this cell that could be in a line of `connect` with it (capped at the board edges).
+    pub fn diag1_neighbourhood(&self, connect: usize) -> impl Iterator<Item = Self> {
+        let reach = connect as isize - 1;
         let start_offset = -min(
-            3,
+            reach,
             min(
                 isize::try_from(self.col).unwrap(),
                 isize::try_from(self.row).unwrap(),
@@ -123,10 +161,11 @@ impl Cell {
         );
 
         let end_offset = min(
-            3,
+            reach,
             min(
-                isize::try_from(column::Idx::MAX).unwrap() - isize::try_from(self.col).unwrap(),
-                isize::try_from(row::Idx::MAX).unwrap() - isize::try_from(self.row).unwrap(),
+                isize::try_from(column::Idx::<W>::MAX).unwrap()
+                    - isize::try_from(self.col).unwrap(),
+                isize::try_from(row::Idx::<H>::MAX).unwrap() - isize::try_from(self.row).unwrap(),
             ),
         );
 
@@ -137,20 +176,22 @@ impl Cell {
     }
 
     /// Returns an iterator over the cells in the same diagonal (top-left to bottom-right)
-    /// as this cell within a distance of 3 (capped at the board edges).
-    pub fn diag2_neighbourhood(&self) -> impl Iterator<Item = Cell> {
+    /// as this cell that could be in a line of `connect` with it (capped at the board edges).
+    pub fn diag2_neighbourhood(&self, connect: usize) -> impl Iterator<Item = Self> {
+        let reach = connect as isize - 1;
         let start_offset = -min(
-            3,
+            reach,
             min(
                 isize::try_from(self.col).unwrap(),
-                isize::try_from(row::Idx::MAX).unwrap() - isize::try_from(self.row).unwrap(),
+                isize::try_from(row::Idx::<H>::MAX).unwrap() - isize::try_from(self.row).unwrap(),
             ),
         );
 
         let end_offset = min(
-            3,
+            reach,
             min(
-                isize::try_from(column::Idx::MAX).unwrap() - isize::try_from(self.col).unwrap(),
+                isize::try_from(column::Idx::<W>::MAX).unwrap()
+                    - isize::try_from(self.col).unwrap(),
                 isize::try_from(self.row).unwrap(),
             ),
         );
@@ -168,17 +209,15 @@ impl Cell {
         }
     }
 
-    /// TODO. (left, right)
+    /// Returns this cell and its mirror image, left first,
+    /// or None if the cell is its own mirror image in the centre column.
     pub fn symm_pair(&self) -> Option<(Self, Self)> {
-        if self.col == column::Idx::CENTRE {
+        let flipped = self.flipped();
+        if self.col == flipped.col {
             return None;
         }
 
-        let flipped = Cell {
-            col: self.col.flipped(),
-            row: self.row,
-        };
-        if usize::from(self.col) < 3 {
+        if self.col < flipped.col {
             Some((*self, flipped))
         } else {
             Some((flipped, *self))
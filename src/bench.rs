@@ -0,0 +1,370 @@
+use crate::algorithms::minimax_basic::{minimax_clone_with, minimax_mut_with};
+use crate::algorithms::minimax_cached::minimax_cached_with;
+use crate::algorithms::minimax_counting::minimax_counting_with;
+use crate::algorithms::minimax_symm::minimax_symm_with;
+use crate::algorithms::pruning::NodeCounter;
+use crate::basic::*;
+use crate::board::array_board::ArrayBoard;
+use crate::board::bit_board::BitBoard;
+use crate::board::symm_board::SymmBoard;
+use crate::board::{Board, CloneBoard, MutBoard};
+use crate::test_positions::{MEDIUM_1, Position};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// The empty board, searched to depth 12.
+pub const EMPTY_12: Position = Position {
+    board: "|.......|
+            |.......|
+            |.......|
+            |.......|
+            |.......|
+            |.......|",
+    winner: None,
+    depth: 12,
+};
+
+/// The solvers that can be benchmarked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solver {
+    Mut,
+    Clone,
+    Cached,
+    Symm,
+    Counting,
+}
+
+impl Solver {
+    pub const ALL: [Solver; 5] = [
+        Solver::Mut,
+        Solver::Clone,
+        Solver::Cached,
+        Solver::Symm,
+        Solver::Counting,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Solver::Mut => "minimax_mut",
+            Solver::Clone => "minimax_clone",
+            Solver::Cached => "minimax_cached",
+            Solver::Symm => "minimax_symm",
+            Solver::Counting => "minimax_counting",
+        }
+    }
+
+    /// Returns the solver with the given name, as given by `name`.
+    pub fn from_name(name: &str) -> Option<Solver> {
+        Solver::ALL.into_iter().find(|solver| solver.name() == name)
+    }
+
+    /// Solves the position, counting the nodes searched with `counter`.
+    pub fn run<B: CloneBoard + MutBoard + Hash>(
+        &self,
+        mut board: B,
+        depth: usize,
+        curr: Token,
+        counter: &NodeCounter,
+    ) -> Option<Token> {
+        match self {
+            Solver::Mut => minimax_mut_with(&mut board, depth, curr, counter),
+            Solver::Clone => minimax_clone_with(board, depth, curr, counter),
+            Solver::Cached => minimax_cached_with(board, depth, curr, counter),
+            Solver::Symm => minimax_symm_with(board, depth, curr, counter),
+            Solver::Counting => minimax_counting_with(board, depth, curr, counter),
+        }
+    }
+}
+
+type Runner = fn(&Position, Solver, &NodeCounter) -> Option<Token>;
+
+/// Reads the position into a board of type `B` and solves it to the position's depth.
+fn runner<B: CloneBoard + MutBoard + Hash>(
+    position: &Position,
+    solver: Solver,
+    counter: &NodeCounter,
+) -> Option<Token> {
+    let board = B::read(position.board);
+    let curr = board.curr_player();
+    solver.run(board, position.depth, curr, counter)
+}
+
+struct Case {
+    board: &'static str,
+    solver: Solver,
+    position: (&'static str, &'static Position),
+    runner: Runner,
+}
+
+/// The timings of one board, solver and position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub board: &'static str,
+    pub solver: &'static str,
+    pub position: &'static str,
+    pub runs: usize,
+    pub median: Duration,
+    pub min: Duration,
+    pub nodes: u64,
+    /// True if the solver found the position's expected winner.
+    pub correct: bool,
+}
+
+impl Record {
+    /// Nodes searched per second, over the median time,
+    /// or None if the median is zero and too short to measure a rate over.
+    pub fn nodes_per_sec(&self) -> Option<f64> {
+        (!self.median.is_zero()).then(|| self.nodes as f64 / self.median.as_secs_f64())
+    }
+}
+
+/// A benchmark over every combination of the board types, solvers and positions added to it.
+pub struct Bench {
+    cases: Vec<Case>,
+    /// The number of times each case is run.
+    pub runs: usize,
+}
+
+impl Bench {
+    pub fn new(runs: usize) -> Self {
+        Bench {
+            cases: Vec::new(),
+            runs,
+        }
+    }
+
+    /// Adds a case for the board type with each of the solvers on each of the positions.
+    pub fn with<B: CloneBoard + MutBoard + Hash>(
+        mut self,
+        board: &'static str,
+        solvers: &[Solver],
+        positions: &[(&'static str, &'static Position)],
+    ) -> Self {
+        for &position in positions {
+            for &solver in solvers {
+                self.cases.push(Case {
+                    board,
+                    solver,
+                    position,
+                    runner: runner::<B>,
+                });
+            }
+        }
+        self
+    }
+
+    /// The standard benchmark: the basic and cached solvers on `MEDIUM_1` with every board,
+    /// and the cached solvers on the empty board with the bit boards.
+    pub fn standard(runs: usize) -> Self {
+        let medium = [("MEDIUM_1", &MEDIUM_1)];
+        let empty = [("EMPTY_12", &EMPTY_12)];
+        let medium_solvers = [Solver::Clone, Solver::Mut, Solver::Cached];
+        let empty_solvers = [Solver::Cached, Solver::Symm, Solver::Counting];
+
+        Bench::new(runs)
+            .with::<ArrayBoard>("ArrayBoard", &medium_solvers, &medium)
+            .with::<BitBoard>("BitBoard", &medium_solvers, &medium)
+            .with::<SymmBoard>("SymmBoard", &medium_solvers, &medium)
+            .with::<BitBoard>("BitBoard", &empty_solvers, &empty)
+            .with::<SymmBoard>("SymmBoard", &empty_solvers, &empty)
+    }
+
+    /// Runs every case, calling `report` with each record as it finishes.
+    pub fn run(&self, mut report: impl FnMut(&Record)) -> Vec<Record> {
+        let mut records = Vec::with_capacity(self.cases.len());
+        for case in &self.cases {
+            let (name, position) = case.position;
+            let mut times = Vec::with_capacity(self.runs);
+            let mut nodes = 0;
+            let mut correct = true;
+
+            for _ in 0..self.runs.max(1) {
+                let counter = NodeCounter::default();
+                let start = Instant::now();
+                let result = (case.runner)(position, case.solver, &counter);
+                times.push(start.elapsed());
+                nodes = counter.nodes();
+                correct &= result == position.winner;
+            }
+            times.sort();
+
+            let record = Record {
+                board: case.board,
+                solver: case.solver.name(),
+                position: name,
+                runs: times.len(),
+                median: times[times.len() / 2],
+                min: times[0],
+                nodes,
+                correct,
+            };
+            report(&record);
+            records.push(record);
+        }
+        records
+    }
+}
+
+const COLUMNS: [&str; 9] = [
+    "board",
+    "solver",
+    "position",
+    "runs",
+    "median_ms",
+    "min_ms",
+    "nodes",
+    "nodes_per_sec",
+    "correct",
+];
+
+fn fields(record: &Record) -> [String; 9] {
+    [
+        record.board.to_owned(),
+        record.solver.to_owned(),
+        record.position.to_owned(),
+        record.runs.to_string(),
+        format!("{:.3}", record.median.as_secs_f64() * 1000.0),
+        format!("{:.3}", record.min.as_secs_f64() * 1000.0),
+        record.nodes.to_string(),
+        // left empty when there is no rate, written as null in JSON
+        record
+            .nodes_per_sec()
+            .map(|rate| format!("{rate:.0}"))
+            .unwrap_or_default(),
+        record.correct.to_string(),
+    ]
+}
+
+/// Formats the records as CSV, with a header row.
+pub fn to_csv(records: &[Record]) -> String {
+    let mut csv = COLUMNS.join(",") + "\n";
+    for record in records {
+        csv += &(fields(record).join(",") + "\n");
+    }
+    csv
+}
+
+/// Formats the records as a JSON array of objects.
+pub fn to_json(records: &[Record]) -> String {
+    let objects: Vec<String> = records
+        .iter()
+        .map(|record| {
+            let values = fields(record);
+            let pairs: Vec<String> = COLUMNS
+                .iter()
+                .zip(values)
+                .enumerate()
+                .map(|(i, (key, value))| {
+                    // the first three columns are names, the rest numbers and booleans
+                    if i < 3 {
+                        format!("\"{key}\": \"{value}\"")
+                    } else if value.is_empty() {
+                        format!("\"{key}\": null")
+                    } else {
+                        format!("\"{key}\": {value}")
+                    }
+                })
+                .collect();
+            format!("  {{{}}}", pairs.join(", "))
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+/// Runs the standard benchmark, printing a table and optionally writing the records to a file.
+/// Usage: `bench [runs] [output.csv | output.json]`, defaulting to 3 runs.
+pub fn main(args: &[String]) {
+    let runs = args
+        .first()
+        .map(|arg| arg.parse().expect("the number of runs should be a number"))
+        .unwrap_or(3);
+
+    println!(
+        "{:<12} {:<18} {:<10} {:>12} {:>12} {:>12} {:>14}",
+        "board", "solver", "position", "median (ms)", "min (ms)", "nodes", "nodes/s"
+    );
+    println!("{:-<96}", "");
+    let records = Bench::standard(runs).run(|record| {
+        println!(
+            "{:<12} {:<18} {:<10} {:>12.2} {:>12.2} {:>12} {:>14}{}",
+            record.board,
+            record.solver,
+            record.position,
+            record.median.as_secs_f64() * 1000.0,
+            record.min.as_secs_f64() * 1000.0,
+            record.nodes,
+            record
+                .nodes_per_sec()
+                .map_or("-".to_owned(), |rate| format!("{rate:.0}")),
+            if record.correct { "" } else { "  INCORRECT" }
+        );
+    });
+
+    if let Some(path) = args.get(1) {
+        let output = if path.ends_with(".json") {
+            to_json(&records)
+        } else {
+            to_csv(&records)
+        };
+        std::fs::write(path, output).expect("the output file should be writable");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_positions::EASY_1;
+
+    fn easy_bench() -> Vec<Record> {
+        Bench::new(3)
+            .with::<BitBoard>("BitBoard", &Solver::ALL, &[("EASY_1", &EASY_1)])
+            .with::<ArrayBoard>("ArrayBoard", &[Solver::Cached], &[("EASY_1", &EASY_1)])
+            .run(|_| ())
+    }
+
+    #[test]
+    fn runs_every_case() {
+        let records = easy_bench();
+        assert_eq!(records.len(), Solver::ALL.len() + 1);
+        for record in &records {
+            assert!(record.correct, "{} gave the wrong answer.", record.solver);
+            assert!(record.nodes > 0);
+            assert!(record.min <= record.median);
+        }
+        // the same solver searches the same tree on every board
+        assert_eq!(records[2].nodes, records[5].nodes);
+    }
+
+    #[test]
+    fn output_formats() {
+        let records = easy_bench();
+        let csv = to_csv(&records);
+        assert_eq!(csv.lines().count(), records.len() + 1);
+        assert!(csv.starts_with("board,solver,position,"));
+
+        let json = to_json(&records);
+        assert_eq!(
+            json.matches("\"board\": \"BitBoard\"").count(),
+            Solver::ALL.len()
+        );
+        assert!(json.contains("\"correct\": true"));
+    }
+
+    #[test]
+    fn zero_time_has_no_rate() {
+        let record = Record {
+            board: "BitBoard",
+            solver: "cached",
+            position: "EASY_1",
+            runs: 1,
+            median: Duration::ZERO,
+            min: Duration::ZERO,
+            nodes: 10,
+            correct: true,
+        };
+        assert_eq!(record.nodes_per_sec(), None);
+        let records = [record];
+        assert!(to_json(&records).contains("\"nodes_per_sec\": null,"));
+        assert!(to_csv(&records).contains(",10,,true"));
+    }
+}
@@ -3,33 +3,53 @@ use std::ops::{Index, IndexMut};
 use crate::basic::*;
 use crate::board::{Board, CloneBoard, MutBoard};
 
-/// MutBoard implementation using a 2D array of Option<Token>.
+/// MutBoard implementation using a 2D array of Option<Piece>, so it can hold blockers.
 /// An array of columns, where the 0th element is the bottom of the column.
+/// Lines wrap round from the last column into the first if `C`, as on a cylinder.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct ArrayBoard {
-    grid: [[Option<Token>; row::COUNT]; column::COUNT],
+pub struct ArrayBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+    const C: bool = false,
+> {
+    grid: [[Option<Piece>; H]; W],
 }
 
-impl Index<&Cell> for ArrayBoard {
-    type Output = Option<Token>;
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Index<&Cell<W, H>>
+    for ArrayBoard<W, H, K, C>
+{
+    type Output = Option<Piece>;
 
-    fn index(&self, index: &Cell) -> &Self::Output {
+    fn index(&self, index: &Cell<W, H>) -> &Self::Output {
         &self.grid[usize::from(index.col)][usize::from(index.row)]
     }
 }
 
-impl IndexMut<&Cell> for ArrayBoard {
-    fn index_mut(&mut self, index: &Cell) -> &mut Self::Output {
+impl<const W: usize, const H: usize, const K: usize, const C: bool> IndexMut<&Cell<W, H>>
+    for ArrayBoard<W, H, K, C>
+{
+    fn index_mut(&mut self, index: &Cell<W, H>) -> &mut Self::Output {
         &mut self.grid[usize::from(index.col)][usize::from(index.row)]
     }
 }
 
-impl Board for ArrayBoard {
-    const EMPTY: Self = ArrayBoard {
-        grid: [[None; row::COUNT]; column::COUNT],
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Board<W, H, K>
+    for ArrayBoard<W, H, K, C>
+{
+    const EMPTY: Self = {
+        assert!(
+            !C || K <= W,
+            "a line cannot be longer than the way round the cylinder"
+        );
+        ArrayBoard {
+            grid: [[None; H]; W],
+        }
     };
 
-    fn can_place(&self, col: &column::Idx) -> bool {
+    const CYLINDER: bool = C;
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
         self[&Cell {
             col: *col,
             row: row::Idx::TOP,
@@ -37,30 +57,63 @@ impl Board for ArrayBoard {
             .is_none()
     }
 
-    fn get(&self, cell: &Cell) -> Option<Token> {
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        match self[cell] {
+            Some(Piece::Token(token)) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
         self[cell]
     }
 
-    fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell> {
-        for row in row::BOTTOM_UP {
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        self.place_piece(col, &Piece::Token(*token))
+    }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.place_piece(col, &Piece::Blocker)
+    }
+
+    fn place_piece(&mut self, col: &column::Idx<W>, piece: &Piece) -> Option<Cell<W, H>> {
+        for row in row::Idx::<H>::BOTTOM_UP {
             if self[&Cell { col: *col, row }].is_none() {
-                self[&Cell { col: *col, row }] = Some(*token);
-                return Some(Cell {
-                    col: *col,
-                    row: row,
-                });
+                self[&Cell { col: *col, row }] = Some(*piece);
+                return Some(Cell { col: *col, row });
             }
         }
         None
     }
 }
 
-impl CloneBoard for ArrayBoard {}
+impl<const W: usize, const H: usize, const K: usize, const C: bool> CloneBoard<W, H, K>
+    for ArrayBoard<W, H, K, C>
+{
+}
 
-impl MutBoard for ArrayBoard {
-    fn unplace(&mut self, cell: &Cell) {
+impl<const W: usize, const H: usize, const K: usize, const C: bool> MutBoard<W, H, K>
+    for ArrayBoard<W, H, K, C>
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
         self[cell] = None;
     }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        let col = &mut self.grid[usize::from(*col)];
+        let Some(Piece::Token(bottom)) = col[0] else {
+            return None;
+        };
+        col.rotate_left(1);
+        col[H - 1] = None;
+        Some(bottom)
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        let col = &mut self.grid[usize::from(*col)];
+        col.rotate_right(1);
+        col[0] = Some(Piece::Token(*token));
+    }
 }
 
 #[cfg(test)]
@@ -68,4 +121,6 @@ mod tests {
     use super::*;
     make_board_tests!(ArrayBoard);
     make_mut_board_tests!(ArrayBoard);
+    make_blocker_tests!(ArrayBoard);
+    make_cylinder_tests!(ArrayBoard<7, 6, 4, true>);
 }
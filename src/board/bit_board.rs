@@ -1,28 +1,60 @@
 use crate::basic::*;
 use crate::board::{Board, CloneBoard, MutBoard, bit_col};
 
-
 /// A board implementation using bit manipulation for storage.
-/// Each column is stored as a BitCol.
+/// Each column is stored as a BitCol, so the board is at most 7 rows high.
+/// Lines wrap round from the last column into the first if `C`, as on a cylinder.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct BitBoard {
-    cols: [bit_col::BitCol; column::COUNT],
+pub struct BitBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+    const C: bool = false,
+> {
+    cols: [bit_col::BitCol<H>; W],
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> BitBoard<W, H, K, C> {
+    const FITS: () = {
+        assert!(H <= 7, "a column does not fit in a BitCol");
+        assert!(
+            !C || K <= W,
+            "a line cannot be longer than the way round the cylinder"
+        );
+    };
 }
-impl Board for BitBoard {
-    const EMPTY: Self = BitBoard {
-        cols: [bit_col::BitCol::EMPTY; column::COUNT],
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Board<W, H, K>
+    for BitBoard<W, H, K, C>
+{
+    const EMPTY: Self = {
+        let () = Self::FITS;
+        BitBoard {
+            cols: [bit_col::BitCol::EMPTY; W],
+        }
     };
 
-    fn get(&self, cell: &Cell) -> Option<Token> {
+    const CYLINDER: bool = C;
+
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
         self.cols[usize::from(cell.col)].get(&cell.row)
     }
 
-    fn can_place(&self, col: &column::Idx) -> bool {
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        self.cols[usize::from(cell.col)].piece(&cell.row)
+    }
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
         !self.cols[usize::from(*col)].is_full()
     }
 
-    fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell> {
-        if self.can_place(col) == false {
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let row = row::Idx::try_from(self.cols[usize::from(*col)].count()).ok()?;
+        Some(Cell { col: *col, row })
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        if !self.can_place(col) {
             return None;
         }
 
@@ -33,14 +65,51 @@ impl Board for BitBoard {
             row: row::Idx::try_from(self.cols[col_idx].count() - 1).unwrap(),
         })
     }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let cell = self.next_cell(col)?;
+        self.cols[usize::from(*col)].force_block();
+        Some(cell)
+    }
 }
 
-impl CloneBoard for BitBoard {}
+impl<const W: usize, const H: usize, const K: usize, const C: bool> CloneBoard<W, H, K>
+    for BitBoard<W, H, K, C>
+{
+    fn flipped(&self) -> Self {
+        let mut cols = self.cols;
+        cols.reverse();
+        BitBoard { cols }
+    }
+
+    fn rotated(&self, by: usize) -> Self {
+        let mut cols = self.cols;
+        cols.rotate_right(by % W);
+        BitBoard { cols }
+    }
+
+    fn canonical(&self) -> Self {
+        BitBoard {
+            cols: bit_col::canonical(self.cols, C),
+        }
+    }
+}
 
-impl MutBoard for BitBoard {
-    fn unplace(&mut self, cell: &Cell) {
+impl<const W: usize, const H: usize, const K: usize, const C: bool> MutBoard<W, H, K>
+    for BitBoard<W, H, K, C>
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
         self.cols[usize::from(cell.col)].force_pop();
     }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        let col = &mut self.cols[usize::from(*col)];
+        (!col.is_empty() && !col.is_blocked(&row::Idx::BOTTOM)).then(|| col.force_pop_bottom())
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        self.cols[usize::from(*col)].force_push_bottom(token);
+    }
 }
 
 #[cfg(test)]
@@ -49,4 +118,6 @@ mod tests {
 
     make_board_tests!(BitBoard);
     make_mut_board_tests!(BitBoard);
+    make_blocker_tests!(BitBoard);
+    make_cylinder_tests!(BitBoard<7, 6, 4, true>);
 }
@@ -14,20 +14,22 @@ fn bit_to_token(bit: u8) -> Token {
     }
 }
 
-/// A column of the BitBoard, stored as a u8.
+/// A column of the BitBoard, `H` rows high, stored as a u8, so `H` is at most 7.
 /// Formatted with a leading 1 bit, followed by the rows from bottom to top,
 /// The top tile is the LSB and the bottom tile is the MSB after the leading 1.
+/// Blockers are marked in a second u8, with the bottom row in the LSB,
+/// and take a 0 bit in the first.
 // Examples:
 // 0b01abcdef : col is full, a at bottom, f at top
 // 0b00000001 : col is empty
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-pub struct BitCol(u8);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BitCol<const H: usize>(u8, u8);
 
-impl BitCol {
+impl<const H: usize> BitCol<H> {
     /// An empty BitCol.
-    pub const EMPTY: Self = BitCol(0b00000001);
+    pub const EMPTY: Self = BitCol(0b00000001, 0);
 
-    /// Counts the number of tokens in the column.
+    /// Counts the number of tokens and blockers in the column.
     /// Also the bit index of the leading one.
     pub fn count(&self) -> usize {
         7 - self.0.leading_zeros() as usize
@@ -38,12 +40,28 @@ impl BitCol {
     }
 
     pub fn is_full(&self) -> bool {
-        self.count() >= row::COUNT
+        self.count() >= H
     }
 
-    /// Gets the token at the given row in the column.
-    pub fn get(&self, row: &row::Idx) -> Option<Token> {
-        if self.count() > usize::from(*row) {
+    /// Returns true if there is a blocker at the given row in the column.
+    pub fn is_blocked(&self, row: &row::Idx<H>) -> bool {
+        self.1 >> usize::from(*row) & 1 == 1
+    }
+
+    /// Gets the piece at the given row in the column.
+    pub fn piece(&self, row: &row::Idx<H>) -> Option<Piece> {
+        if self.is_blocked(row) {
+            Some(Piece::Blocker)
+        } else {
+            self.get(row).map(Piece::Token)
+        }
+    }
+
+    /// Gets the token at the given row in the column, or None if it is empty or blocked.
+    pub fn get(&self, row: &row::Idx<H>) -> Option<Token> {
+        if self.is_blocked(row) {
+            None
+        } else if self.count() > usize::from(*row) {
             // The bit index of the desired row
             let bit_idx = self.count() - usize::from(*row) - 1;
             let bit_mask = 1 << bit_idx;
@@ -57,6 +75,7 @@ impl BitCol {
     /// Debug asserts that the column is not empty.
     pub fn force_pop(&mut self) {
         debug_assert!(!self.is_empty(), "Tried to pop from an empty column.");
+        self.1 &= !(1 << (self.count() - 1));
         self.0 >>= 1;
     }
 
@@ -70,8 +89,69 @@ impl BitCol {
         self.0 |= token_bit;
     }
 
+    /// Push a blocker onto the column.
+    /// Debug asserts that the column is not full.
+    pub fn force_block(&mut self) {
+        debug_assert!(!self.is_full(), "Tried to push onto a full column.");
+
+        self.1 |= 1 << self.count();
+        self.0 <<= 1;
+    }
+
+    /// Pop the bottom token from the column, moving the rest down a row.
+    /// Debug asserts that the column is not empty.
+    pub fn force_pop_bottom(&mut self) -> Token {
+        debug_assert!(!self.is_empty(), "Tried to pop from an empty column.");
+        // the bottom bit becomes the leading one
+        let bottom = self.count() - 1;
+        let token = bit_to_token(self.0 >> bottom & 1);
+        self.0 = self.0 & ((1 << bottom) - 1) | 1 << bottom;
+        self.1 >>= 1;
+        token
+    }
+
+    /// Push a token onto the bottom of the column, moving the rest up a row.
+    /// Debug asserts that the column is not full.
+    pub fn force_push_bottom(&mut self, token: &Token) {
+        debug_assert!(!self.is_full(), "Tried to push onto a full column.");
+
+        let count = self.count();
+        let token_bit = token_to_bit(token);
+        self.0 = self.0 & ((1 << count) - 1) | token_bit << count | 1 << (count + 1);
+        self.1 <<= 1;
+    }
+
     /// Returns the underlying u8 value of the column.
     pub fn as_u8(&self) -> u8 {
         self.0
     }
+
+    /// Returns the rows holding blockers, with the bottom row in the lowest bit.
+    pub fn blockers(&self) -> u8 {
+        self.1
+    }
+}
+
+/// Returns the least of the columns and their mirror image, or on a cylinder of every
+/// rotation of either, so that equivalent boards have the same columns.
+/// Only images with the blockers where they are count, as blockers never move.
+pub fn canonical<const W: usize, const H: usize>(
+    cols: [BitCol<H>; W],
+    cylinder: bool,
+) -> [BitCol<H>; W] {
+    let mut flipped = cols;
+    flipped.reverse();
+    let turns = if cylinder { W } else { 1 };
+
+    let blockers = cols.map(|col| col.blockers());
+    let mut least = cols;
+    for mut turned in [cols, flipped] {
+        for _ in 0..turns {
+            if turned.map(|col| col.blockers()) == blockers {
+                least = least.min(turned);
+            }
+            turned.rotate_right(1);
+        }
+    }
+    least
 }
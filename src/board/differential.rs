@@ -0,0 +1,363 @@
+use crate::basic::*;
+use crate::board::array_board::ArrayBoard;
+use crate::board::bit_board::BitBoard;
+use crate::board::moves::Moves;
+use crate::board::symm_board::SymmBoard;
+use crate::board::wide_bit_board::WideBitBoard;
+use crate::board::{Board, MutBoard};
+use crate::random::Rng;
+use std::fmt::{Debug, Display, Formatter};
+
+/// Object safe view of a `MutBoard` `W` columns wide and `H` rows high, won with `K` in a line,
+/// so boards of different types can be driven together.
+/// Each method calls the `Board` or `MutBoard` method of the same name without the `dyn_`,
+/// which keeps the two apart when both traits are in scope.
+pub trait Subject<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+>
+{
+    fn dyn_get(&self, cell: &Cell<W, H>) -> Option<Token>;
+    fn dyn_can_place(&self, col: &column::Idx<W>) -> bool;
+    fn dyn_place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>>;
+    fn dyn_next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>>;
+    fn dyn_unplace(&mut self, cell: &Cell<W, H>);
+    fn dyn_would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool;
+    fn dyn_won_at(&self, cell: &Cell<W, H>) -> bool;
+    fn dyn_count_adjacent_at(&self, cell: &Cell<W, H>) -> Option<(usize, usize)>;
+    /// Resets the board to `EMPTY`.
+    fn reset(&mut self);
+}
+
+impl<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>> Subject<W, H, K> for B {
+    fn dyn_get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        self.get(cell)
+    }
+
+    fn dyn_can_place(&self, col: &column::Idx<W>) -> bool {
+        self.can_place(col)
+    }
+
+    fn dyn_place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        self.place(col, token)
+    }
+
+    fn dyn_next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.next_cell(col)
+    }
+
+    fn dyn_unplace(&mut self, cell: &Cell<W, H>) {
+        self.unplace(cell)
+    }
+
+    fn dyn_would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool {
+        self.would_win_at(cell, token)
+    }
+
+    fn dyn_won_at(&self, cell: &Cell<W, H>) -> bool {
+        self.won_at(cell)
+    }
+
+    fn dyn_count_adjacent_at(&self, cell: &Cell<W, H>) -> Option<(usize, usize)> {
+        self.count_adjacent_at(cell)
+    }
+
+    fn reset(&mut self) {
+        *self = B::EMPTY;
+    }
+}
+
+/// The first point where the boards disagree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence<const W: usize = { column::COUNT }> {
+    /// The columns played from the empty board, the last of which shows the divergence.
+    /// Moves into full columns are kept, since they check `can_place` and `place`.
+    pub moves: Vec<column::Idx<W>>,
+    /// The operation the boards disagree on.
+    pub check: String,
+    /// The result of the operation on each board.
+    pub results: Vec<(&'static str, String)>,
+}
+
+impl<const W: usize> Display for Divergence<W> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let moves: Vec<String> = self.moves.iter().map(|col| col.to_string()).collect();
+        writeln!(
+            f,
+            "boards disagree on `{}` after moves [{}]",
+            self.check,
+            moves.join(", ")
+        )?;
+        for (name, result) in &self.results {
+            writeln!(f, "    {name}: {result}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays the same seeded random games on several board implementations at once,
+/// checking they agree after every move. The boards are `W` columns wide and `H` rows high,
+/// won with `K` in a line.
+#[derive(Default)]
+pub struct Harness<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+> {
+    boards: Vec<(&'static str, Box<dyn Subject<W, H, K>>)>,
+}
+
+impl Harness {
+    /// A harness with every board implementation in the crate, on the standard board.
+    pub fn all() -> Self {
+        Harness::new()
+            .with::<ArrayBoard>("ArrayBoard")
+            .with::<BitBoard>("BitBoard")
+            .with::<SymmBoard>("SymmBoard")
+            .with::<WideBitBoard>("WideBitBoard")
+            .with::<Moves>("Moves")
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize> Harness<W, H, K> {
+    /// A harness with no boards.
+    pub fn new() -> Self {
+        Harness { boards: Vec::new() }
+    }
+
+    /// Adds a board implementation to the harness.
+    pub fn with<B: MutBoard<W, H, K> + 'static>(mut self, name: &'static str) -> Self {
+        self.boards.push((name, Box::new(B::EMPTY)));
+        self
+    }
+
+    /// Plays `games` random games from the given seed, returning the first divergence found,
+    /// shrunk to a minimal move sequence. A harness with no boards has nothing to disagree on.
+    pub fn run(&mut self, seed: u64, games: usize) -> Result<(), Divergence<W>> {
+        if self.boards.is_empty() {
+            return Ok(());
+        }
+        let mut rng = Rng::seeded(seed);
+        for _ in 0..games {
+            let moves = random_game::<W, H, K>(&mut rng);
+            if self.replay(&moves).is_err() {
+                return Err(self.shrink(moves));
+            }
+        }
+        Ok(())
+    }
+
+    /// Shrinks a failing move sequence: first to its shortest failing prefix,
+    /// then by removing single moves while it still fails.
+    fn shrink(&mut self, mut moves: Vec<column::Idx<W>>) -> Divergence<W> {
+        let mut divergence = self.replay(&moves).expect_err("the moves should fail");
+        moves = divergence.moves.clone();
+
+        let mut i = 0;
+        while i < moves.len() {
+            let mut fewer = moves.clone();
+            fewer.remove(i);
+            match self.replay(&fewer) {
+                Err(smaller) => {
+                    moves = smaller.moves.clone();
+                    divergence = smaller;
+                }
+                Ok(()) => i += 1,
+            }
+        }
+        divergence
+    }
+
+    /// Plays the moves on every board from empty, checking them after each move.
+    fn replay(&mut self, moves: &[column::Idx<W>]) -> Result<(), Divergence<W>> {
+        for (_, board) in &mut self.boards {
+            board.reset();
+        }
+
+        let mut curr = Token::START;
+        for (i, col) in moves.iter().enumerate() {
+            let played = &moves[..=i];
+            self.agree(played, format!("can_place({col})"), |b| {
+                b.dyn_can_place(col)
+            })?;
+            let Some(cell) =
+                self.agree(played, format!("place({col})"), |b| b.dyn_place(col, &curr))?
+            else {
+                continue;
+            };
+
+            self.agree(played, format!("won_at({cell:?})"), |b| b.dyn_won_at(&cell))?;
+            self.agree(played, format!("count_adjacent_at({cell:?})"), |b| {
+                b.dyn_count_adjacent_at(&cell)
+            })?;
+
+            // unplacing and placing again should give the same board
+            for (_, board) in &mut self.boards {
+                board.dyn_unplace(&cell);
+            }
+            self.check_cells(played, "unplace")?;
+            self.agree(played, format!("place({col}) after unplace"), |b| {
+                b.dyn_place(col, &curr)
+            })?;
+            self.check_cells(played, "place")?;
+            self.check_wins(played)?;
+
+            curr = curr.next();
+        }
+        Ok(())
+    }
+
+    /// Checks the boards agree on every cell, and on where the next token in each column goes.
+    fn check_cells(&mut self, played: &[column::Idx<W>], after: &str) -> Result<(), Divergence<W>> {
+        for col in column::Idx::<W>::ALL {
+            self.agree(played, format!("next_cell({col}) after {after}"), |b| {
+                b.dyn_next_cell(&col)
+            })?;
+            for row in row::Idx::<H>::BOTTOM_UP {
+                let cell = Cell { col, row };
+                self.agree(played, format!("get({cell:?}) after {after}"), |b| {
+                    b.dyn_get(&cell)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the boards agree on which empty cells would win for each player.
+    fn check_wins(&mut self, played: &[column::Idx<W>]) -> Result<(), Divergence<W>> {
+        for col in column::Idx::<W>::ALL {
+            for row in row::Idx::<H>::BOTTOM_UP {
+                let cell = Cell { col, row };
+                if self
+                    .boards
+                    .iter()
+                    .any(|(_, board)| board.dyn_get(&cell).is_some())
+                {
+                    continue;
+                }
+                for token in [Token::Yellow, Token::Red] {
+                    self.agree(played, format!("would_win_at({cell:?}, {token:?})"), |b| {
+                        b.dyn_would_win_at(&cell, &token)
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the operation on every board, returning its result if they all agree.
+    /// The harness must have boards, as `run` checks.
+    fn agree<T: PartialEq + Debug>(
+        &mut self,
+        played: &[column::Idx<W>],
+        check: String,
+        mut op: impl FnMut(&mut dyn Subject<W, H, K>) -> T,
+    ) -> Result<T, Divergence<W>> {
+        let results: Vec<(&'static str, T)> = self
+            .boards
+            .iter_mut()
+            .map(|(name, board)| (*name, op(board.as_mut())))
+            .collect();
+
+        if results.windows(2).all(|pair| pair[0].1 == pair[1].1) {
+            let (_, first) = results.into_iter().next().expect("the harness has boards");
+            Ok(first)
+        } else {
+            Err(Divergence {
+                moves: played.to_vec(),
+                check,
+                results: results
+                    .into_iter()
+                    .map(|(name, result)| (name, format!("{result:?}")))
+                    .collect(),
+            })
+        }
+    }
+}
+
+/// Plays a random game, stopping at a win or once the board is full,
+/// and returns the columns chosen.
+fn random_game<const W: usize, const H: usize, const K: usize>(
+    rng: &mut Rng,
+) -> Vec<column::Idx<W>> {
+    let mut board = ArrayBoard::<W, H, K>::EMPTY;
+    let mut curr = Token::START;
+    let mut moves = Vec::new();
+
+    while !board.is_full() {
+        // full columns can be chosen, to check `can_place` and `place` fail together
+        let col = *rng
+            .choose(&column::Idx::<W>::ALL)
+            .expect("there are columns");
+        moves.push(col);
+        if let Some(cell) = board.place(&col, &curr) {
+            if board.won_at(&cell) {
+                break;
+            }
+            curr = curr.next();
+        }
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A board that flips the colour of the token in the top row of the first column.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Leaky(ArrayBoard);
+
+    impl Board for Leaky {
+        const EMPTY: Self = Leaky(ArrayBoard::EMPTY);
+
+        fn get(&self, cell: &Cell) -> Option<Token> {
+            if cell.col == column::Idx::ZERO && cell.row == row::Idx::TOP {
+                self.0.get(cell).map(|token| token.next())
+            } else {
+                self.0.get(cell)
+            }
+        }
+
+        fn can_place(&self, col: &column::Idx) -> bool {
+            self.0.can_place(col)
+        }
+
+        fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell> {
+            self.0.place(col, token)
+        }
+    }
+
+    impl MutBoard for Leaky {
+        fn unplace(&mut self, cell: &Cell) {
+            self.0.unplace(cell)
+        }
+    }
+
+    #[test]
+    fn boards_agree() {
+        assert_eq!(Harness::all().run(0, 2_000), Ok(()));
+    }
+
+    #[test]
+    fn empty_harness_agrees() {
+        assert_eq!(<Harness>::new().run(0, 10), Ok(()));
+        assert_eq!(
+            Harness::new().with::<BitBoard>("BitBoard").run(0, 10),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn divergence_is_minimal() {
+        let mut harness = Harness::new()
+            .with::<BitBoard>("BitBoard")
+            .with::<Leaky>("Leaky");
+        let divergence = harness.run(0, 2_000).unwrap_err();
+
+        assert_eq!(divergence.moves, [column::Idx::ZERO; row::COUNT]);
+        assert_eq!(divergence.results[0].0, "BitBoard");
+        assert_eq!(divergence.results[1].0, "Leaky");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::basic::*;
+use crate::board::{Board, CloneBoard, MutBoard};
+
+/// Any board played by misère rules: the player who completes a line loses.
+/// Every solver and evaluator reads the rules from `Board::MISERE`,
+/// so wrapping a board is enough to solve it the other way round.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Misere<B>(pub B);
+
+impl<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>> Board<W, H, K>
+    for Misere<B>
+{
+    const EMPTY: Self = Misere(B::EMPTY);
+
+    const CYLINDER: bool = B::CYLINDER;
+
+    const MISERE: bool = true;
+
+    const MIRROR_EQ: bool = B::MIRROR_EQ;
+
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        self.0.get(cell)
+    }
+
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        self.0.piece(cell)
+    }
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
+        self.0.can_place(col)
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        self.0.place(col, token)
+    }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.0.block(col)
+    }
+
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.0.next_cell(col)
+    }
+
+    fn win_masks() -> &'static [u128] {
+        B::win_masks()
+    }
+
+    fn mirror_symmetric() -> bool {
+        B::mirror_symmetric()
+    }
+
+    fn would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool {
+        self.0.would_win_at(cell, token)
+    }
+
+    fn won_at(&self, cell: &Cell<W, H>) -> bool {
+        self.0.won_at(cell)
+    }
+
+    fn count_adjacent_at(&self, cell: &Cell<W, H>) -> Option<(usize, usize)> {
+        self.0.count_adjacent_at(cell)
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K>> CloneBoard<W, H, K>
+    for Misere<B>
+{
+    fn flipped(&self) -> Self {
+        Misere(self.0.flipped())
+    }
+
+    fn rotated(&self, by: usize) -> Self {
+        Misere(self.0.rotated(by))
+    }
+
+    fn canonical(&self) -> Self {
+        Misere(self.0.canonical())
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>> MutBoard<W, H, K>
+    for Misere<B>
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
+        self.0.unplace(cell)
+    }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        self.0.pop(col)
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        self.0.unpop(col, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::GameStatus;
+    use crate::board::bit_board::BitBoard;
+
+    make_board_tests!(Misere<BitBoard>);
+    make_mut_board_tests!(Misere<BitBoard>);
+
+    #[test]
+    fn completing_a_line_loses() {
+        let mut board = Misere(BitBoard::<3, 3, 3>::read(
+            "|...|
+             |RR.|
+             |YY.|",
+        ));
+        let cell = board.place(&column::Idx::raw(2), &Token::Yellow).unwrap();
+        assert_eq!(
+            board.status(&cell, Token::Yellow),
+            GameStatus::Won(Token::Red)
+        );
+        assert_eq!(
+            board.0.status(&cell, Token::Yellow),
+            GameStatus::Won(Token::Yellow)
+        );
+
+        board.unplace(&cell);
+        let cell = board.place(&column::Idx::raw(0), &Token::Yellow).unwrap();
+        assert_eq!(board.status(&cell, Token::Yellow), GameStatus::Ongoing);
+
+        let board = Misere(BitBoard::<3, 3, 3>::read(
+            "|RYR|
+             |YRY|
+             |YRY|",
+        ));
+        let cell = Cell {
+            col: column::Idx::raw(2),
+            row: row::Idx::TOP,
+        };
+        assert_eq!(board.status(&cell, Token::Red), GameStatus::Drawn);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
-    basic::{Cell, Token, column, row},
+    basic::{BLOCKER, CONNECT, Cell, Piece, Token, column, row},
     board::moves::Moves,
+    lines::Lines,
 };
 use std::fmt::{Debug, Display};
 
@@ -9,24 +10,57 @@ pub mod testing;
 pub mod array_board;
 pub mod bit_board;
 mod bit_col;
+pub mod differential;
+pub mod misere;
 pub mod moves;
+pub mod patterned;
 pub mod symm_board;
+pub mod wide_bit_board;
+
+/// The state of a game after a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Won(Token),
+    Drawn,
+}
 
-/// Trait containing common board functionality.
-pub trait Board: Debug + Sized + Eq {
+/// Trait containing common board functionality, for a board `W` columns wide and `H` rows high,
+/// won with `K` same-colour tokens in a line. Defaults to the standard 7x6 connect four.
+pub trait Board<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+>: Debug + Sized + Eq
+{
     /// An empty starting board.
     const EMPTY: Self;
 
-    /// Returns the token at the given cell, or None if the cell is empty.
-    fn get(&self, cell: &Cell) -> Option<Token>;
+    /// True if lines run on round from the last column into the first, as on a cylinder.
+    const CYLINDER: bool = false;
+
+    /// True if the player who completes a line loses instead of winning, as in misère play.
+    const MISERE: bool = false;
+
+    /// True if a board compares equal to its mirror image, as `SymmBoard` does,
+    /// so it can only be won with ways to win that are the same in the mirror image.
+    const MIRROR_EQ: bool = false;
+
+    /// Returns the token at the given cell, or None if the cell is empty or blocked.
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token>;
+
+    /// Returns the piece at the given cell, or None if the cell is empty.
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        self.get(cell).map(Piece::Token)
+    }
 
     /// Compute the current player based on the number of tokens on the board.
     fn curr_player(&self) -> Token {
         let mut red_count = 0;
         let mut yellow_count = 0;
 
-        for row in row::BOTTOM_UP {
-            for col in column::IDXS {
+        for row in row::Idx::<H>::BOTTOM_UP {
+            for col in column::Idx::<W>::ALL {
                 let cell = Cell { col, row };
                 match self.get(&cell) {
                     Some(Token::Red) => red_count += 1,
@@ -45,57 +79,161 @@ pub trait Board: Debug + Sized + Eq {
 
     /// Returns true if a token can be placed in the given column.
     /// i.e. the column is not full.
-    fn can_place(&self, col: &column::Idx) -> bool;
+    fn can_place(&self, col: &column::Idx<W>) -> bool;
 
     /// Tries to place the given token in the given column.
     /// Returns `Some(Cell)` if successful, `None` if the column is full.
     /// `token` should equal the current player, as given by `curr_player`.
-    fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell>;
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>>;
+
+    /// Tries to place a blocker in the given column, for setting up a starting position.
+    /// Returns `Some(Cell)` if successful, `None` if the column is full
+    /// or the board cannot hold blockers.
+    fn block(&mut self, _col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        None
+    }
+
+    /// Places the piece in the given column, as `place` or `block` does.
+    fn place_piece(&mut self, col: &column::Idx<W>, piece: &Piece) -> Option<Cell<W, H>> {
+        match piece {
+            Piece::Token(token) => self.place(col, token),
+            Piece::Blocker => self.block(col),
+        }
+    }
+
+    /// Returns true if both boards have blockers on exactly the same cells.
+    /// Blockers never move, so boards with different blockers are never equivalent.
+    fn same_blockers(&self, other: &Self) -> bool {
+        column::Idx::<W>::ALL
+            .iter()
+            .flat_map(|&col| row::Idx::<H>::BOTTOM_UP.map(move |row| Cell { col, row }))
+            .all(|cell| {
+                (self.piece(&cell) == Some(Piece::Blocker))
+                    == (other.piece(&cell) == Some(Piece::Blocker))
+            })
+    }
+
+    /// Returns the cell a token placed in the given column would land in,
+    /// or None if the column is full.
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        row::Idx::<H>::BOTTOM_UP
+            .map(|row| Cell { col: *col, row })
+            .find(|cell| self.piece(cell).is_none())
+    }
+
+    /// Shifts the cell by (col, row), returning None if it leaves the board.
+    /// On a cylinder, wraps round from the last column to the first and back.
+    fn shift(cell: &Cell<W, H>, by: (isize, isize)) -> Option<Cell<W, H>> {
+        if Self::CYLINDER {
+            cell.try_shift_around(by)
+        } else {
+            cell.try_shift(by)
+        }
+    }
+
+    /// Returns the bitmask of every way to win, in the form of `Lines::masks`:
+    /// by default the lines of `K`, wrapping round on a cylinder.
+    fn win_masks() -> &'static [u128] {
+        Lines::<W, H, K>::masks(Self::CYLINDER)
+    }
+
+    /// Returns true if the ways to win are the same in the mirror image, so a board and its
+    /// mirror image are equivalent, as they always are with lines of `K`.
+    fn mirror_symmetric() -> bool {
+        true
+    }
+
+    /// Checks if placing `token` at the given empty cell would make a sequence of `K`.
+    fn would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool {
+        // right, down, to bottom-right, to bottom-left
+        let dirs = [(1, 0), (0, -1), (1, -1), (-1, -1)];
 
-    /// Checks there is a win, a sequence of four same-colour tokens, that includes the given
+        dirs.iter().any(|&(dc, dr)| {
+            let mut count = 1;
+            for dir in [(dc, dr), (-dc, -dr)] {
+                let mut next = Self::shift(cell, dir);
+                while let Some(curr) = next
+                    && self.get(&curr) == Some(*token)
+                {
+                    count += 1;
+                    next = Self::shift(&curr, dir);
+                }
+            }
+            count >= K
+        })
+    }
+
+    /// Returns true if no more tokens can be placed, i.e. every column is full.
+    fn is_full(&self) -> bool {
+        column::Idx::<W>::ALL.iter().all(|col| !self.can_place(col))
+    }
+
+    /// Checks there is a win, a sequence of `K` same-colour tokens, that includes the given
     /// cell. The winning player is given by the colour of the token at the cell.
-    fn won_at(&self, cell: &Cell) -> bool {
+    fn won_at(&self, cell: &Cell<W, H>) -> bool {
         self.count_adjacent_at(cell).is_none()
     }
 
+    /// Returns the winner when `curr` completes a line: `curr` itself, or in misère play
+    /// the other player.
+    fn line_winner(curr: Token) -> Token {
+        if Self::MISERE { curr.next() } else { curr }
+    }
+
+    /// Returns the status of the game after `curr` has placed a token at the given cell.
+    fn status(&self, cell: &Cell<W, H>, curr: Token) -> GameStatus {
+        if self.won_at(cell) {
+            GameStatus::Won(Self::line_winner(curr))
+        } else if self.is_full() {
+            GameStatus::Drawn
+        } else {
+            GameStatus::Ongoing
+        }
+    }
+
     /// For every direction (horizontal, vertical, and both diagonals),
     /// it calculates the length of line of same tokens in that direction,
     /// including the given cell.
-    /// Returns None if a win is found (a row of four), else
-    /// Some((count of adjacent triples, count of adjacent pairs))
-    fn count_adjacent_at(&self, cell: &Cell) -> Option<(usize, usize)> {
-        fn count_line(board: &impl Board, cell: &Cell, dir: (isize, isize)) -> usize {
+    /// Returns None if a win is found (a row of `K`), else
+    /// Some((count of lines of `K - 1`, count of lines of `K - 2`)),
+    /// which are the triples and pairs of connect four.
+    fn count_adjacent_at(&self, cell: &Cell<W, H>) -> Option<(usize, usize)> {
+        fn count_line<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+            board: &B,
+            cell: &Cell<W, H>,
+            dir: (isize, isize),
+        ) -> usize {
             let Some(token) = board.get(cell) else {
                 panic!("Tried to count adjacent at an empty cell.");
             };
 
-            let mut next = cell.try_shift(dir);
+            let mut next = B::shift(cell, dir);
             let mut count = 1;
 
             while let Some(curr) = next {
                 if board.get(&curr) == Some(token) {
                     count += 1;
-                    if (count >= 4) {
+                    if (count >= K) {
                         return count;
                     }
                 } else {
                     break;
                 }
-                next = curr.try_shift(dir);
+                next = B::shift(&curr, dir);
             }
             let dir = (-dir.0, -dir.1);
-            let mut next = cell.try_shift(dir);
+            let mut next = B::shift(cell, dir);
 
             while let Some(curr) = next {
                 if board.get(&curr) == Some(token) {
                     count += 1;
-                    if (count >= 4) {
+                    if (count >= K) {
                         return count;
                     }
                 } else {
                     break;
                 }
-                next = curr.try_shift(dir);
+                next = B::shift(&curr, dir);
             }
             count
         }
@@ -109,9 +247,9 @@ pub trait Board: Debug + Sized + Eq {
 
         for dir in dirs {
             match count_line(self, cell, dir) {
-                2 => counts.1 += 1,
-                3 => counts.0 += 1,
-                4 => return None,
+                n if n >= K => return None,
+                n if n + 1 == K => counts.0 += 1,
+                n if n + 2 == K => counts.1 += 1,
                 _ => (),
             }
         }
@@ -121,19 +259,20 @@ pub trait Board: Debug + Sized + Eq {
     fn to_string(&self) -> String {
         let mut string = "\n".to_owned();
 
-        for row in row::BOTTOM_UP.rev() {
+        for row in row::Idx::<H>::BOTTOM_UP.rev() {
             string.push('|');
-            for col in column::IDXS {
+            for col in column::Idx::<W>::ALL {
                 let cell = Cell { col, row };
-                match self.get(&cell) {
-                    Some(Token::Red) => string.push('R'),
-                    Some(Token::Yellow) => string.push('Y'),
+                match self.piece(&cell) {
+                    Some(Piece::Token(Token::Red)) => string.push('R'),
+                    Some(Piece::Token(Token::Yellow)) => string.push('Y'),
+                    Some(Piece::Blocker) => string.push(BLOCKER),
                     None => string.push(' '),
                 }
             }
             string.push_str("|\n");
         }
-        string.push_str("+--------+\n");
+        string.push_str(&format!("+{}+\n", "-".repeat(W + 1)));
 
         string
     }
@@ -144,6 +283,7 @@ pub trait Board: Debug + Sized + Eq {
     }
 
     /// Read a board from a string representation.
+    /// Blockers are read from `BLOCKER` characters.
     fn read(string: &str) -> Self {
         let mut board = Self::EMPTY;
         let mut diff = 0;
@@ -152,7 +292,7 @@ pub trait Board: Debug + Sized + Eq {
             if line.trim().is_empty() {
                 continue;
             }
-            debug_assert!(line.len() == 7);
+            debug_assert!(line.len() == W);
 
             for (i, ch) in line.chars().enumerate() {
                 let token = match ch {
@@ -165,6 +305,11 @@ pub trait Board: Debug + Sized + Eq {
                         Token::Red
                     }
                     '.' | ' ' => continue,
+                    BLOCKER => {
+                        let cell = board.block(&column::Idx::try_from(i).unwrap());
+                        assert!(cell.is_some(), "blocker cannot be placed in board string");
+                        continue;
+                    }
                     '+' | '-' => return board, // end of board representation
                     _ => panic!("Invalid character in board string: {}", ch),
                 };
@@ -173,14 +318,17 @@ pub trait Board: Debug + Sized + Eq {
             }
         }
 
-        assert!(diff == 0 || diff == 1, "incorrect difference in yellow and red tokens");
+        assert!(
+            diff == 0 || diff == 1,
+            "incorrect difference in yellow and red tokens"
+        );
         board
     }
 
-    fn from_moves(moves: &Moves) -> Self {
+    fn from_moves(moves: &Moves<W, H, K>) -> Self {
         let mut board = Self::EMPTY;
-        for (col, token) in moves.moves.iter() {
-            board.place(&col, &token).unwrap();
+        for (col, piece) in moves.moves.iter() {
+            board.place_piece(col, piece).unwrap();
         }
         board
     }
@@ -188,19 +336,20 @@ pub trait Board: Debug + Sized + Eq {
 
 /// Trait for board implementations that have a cheap clone operation.
 /// Must opt-in to this trait.
-pub trait CloneBoard: Board + Clone {
+pub trait CloneBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+>: Board<W, H, K> + Clone
+{
     /// Clones the given board and calls `try_place` with the given column,
     /// returning the new board and cell if successful.
     /// `token` should equal the current player, as given by `curr_player`.
-    fn clone_and_place(&self, col: &column::Idx, token: &Token) -> Option<(Self, Cell)> {
+    fn clone_and_place(&self, col: &column::Idx<W>, token: &Token) -> Option<(Self, Cell<W, H>)> {
         // check if we can place first to avoid cloning unnecessarily
         if self.can_place(col) {
             let mut new_board = self.clone();
-            if let Some(cell) = new_board.place(col, token) {
-                Some((new_board, cell))
-            } else {
-                None
-            }
+            new_board.place(col, token).map(|cell| (new_board, cell))
         } else {
             None
         }
@@ -208,48 +357,154 @@ pub trait CloneBoard: Board + Clone {
 
     /// Returns an iterator over every possible subsequent board state
     /// after placing the given token in each non-full column.
-    /// Ordered by column::IDXS_CENTRED_FIRST.
-    fn next_boards(&self, token: &Token) -> impl Iterator<Item = (Self, Cell)> {
+    /// Ordered by column::Idx::CENTRED_FIRST.
+    fn next_boards(&self, token: &Token) -> impl Iterator<Item = (Self, Cell<W, H>)> {
         // a simple optimisation to try the centre columns first
-        column::IDXS_CENTRED_FIRST
+        column::Idx::<W>::CENTRED_FIRST
             .iter()
-            .filter_map(move |col| self.clone_and_place(&col, token))
+            .filter_map(move |col| self.clone_and_place(col, token))
     }
 
     /// TODO
     fn flipped(&self) -> Self {
         let mut board = Self::EMPTY;
-        for col in column::IDXS {
-            for row in row::BOTTOM_UP {
-                if let Some(token) = self.get(&Cell { col, row }) {
-                    board.place(&col.flipped(), &token);
+        for col in column::Idx::<W>::ALL {
+            for row in row::Idx::<H>::BOTTOM_UP {
+                if let Some(piece) = self.piece(&Cell { col, row }) {
+                    board.place_piece(&col.flipped(), &piece);
+                }
+            }
+        }
+
+        board
+    }
+
+    /// Returns the board turned `by` columns to the right, each column moving round from
+    /// the last to the first. Only equivalent to the board on a cylinder.
+    fn rotated(&self, by: usize) -> Self {
+        let mut board = Self::EMPTY;
+        for col in column::Idx::<W>::ALL {
+            for row in row::Idx::<H>::BOTTOM_UP {
+                if let Some(piece) = self.piece(&Cell { col, row }) {
+                    board.place_piece(&column::Idx::raw((usize::from(col) + by) % W), &piece);
                 }
             }
         }
 
         board
     }
+
+    /// Returns the same board for every board equivalent to this one: the board or its
+    /// mirror image (if `mirror_symmetric`), or on a cylinder any rotation of either,
+    /// with its blockers in the same cells.
+    fn canonical(&self) -> Self {
+        let cells = |board: &Self| -> Vec<Option<Piece>> {
+            column::Idx::<W>::ALL
+                .iter()
+                .flat_map(|&col| row::Idx::<H>::BOTTOM_UP.map(move |row| Cell { col, row }))
+                .map(|cell| board.piece(&cell))
+                .collect()
+        };
+        let turns = if Self::CYLINDER { W } else { 1 };
+        let mirrors = if Self::mirror_symmetric() { 2 } else { 1 };
+
+        [self.clone(), self.flipped()][..mirrors]
+            .iter()
+            .flat_map(|board| (0..turns).map(|by| board.rotated(by)))
+            .filter(|board| board.same_blockers(self))
+            .min_by_key(cells)
+            .expect("there is at least the board itself")
+    }
 }
 
 /// Trait for board implementations that don't have a cheap clone operation
 /// and instead place and unplace tokens on the same board.
-pub trait MutBoard: Board {
-    /// Removes the token at the given cell, modifying the board in place.
-    /// Does not check if there is a token at the cell.
-    fn unplace(&mut self, cell: &Cell);
+pub trait MutBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+>: Board<W, H, K>
+{
+    /// Removes the token or blocker at the given cell, modifying the board in place.
+    /// Does not check if there is a piece at the cell.
+    fn unplace(&mut self, cell: &Cell<W, H>);
+
+    /// Removes the token at the bottom of the given column, moving every piece above it
+    /// down a row, as in PopOut. Returns the token removed, or None if the column is empty
+    /// or a blocker is at the bottom.
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        let pieces: Vec<Piece> = row::Idx::<H>::BOTTOM_UP
+            .map_while(|row| self.piece(&Cell { col: *col, row }))
+            .collect();
+        let (&Piece::Token(bottom), rest) = pieces.split_first()? else {
+            return None;
+        };
+
+        for row in (0..pieces.len()).rev() {
+            self.unplace(&Cell {
+                col: *col,
+                row: row::Idx::raw(row),
+            });
+        }
+        for piece in rest {
+            self.place_piece(col, piece);
+        }
+        Some(bottom)
+    }
+
+    /// Puts the token back at the bottom of the given column, moving every piece in it
+    /// up a row, undoing `pop`. Does not check the column has room.
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        let pieces: Vec<Piece> = row::Idx::<H>::BOTTOM_UP
+            .map_while(|row| self.piece(&Cell { col: *col, row }))
+            .collect();
+
+        for row in (0..pieces.len()).rev() {
+            self.unplace(&Cell {
+                col: *col,
+                row: row::Idx::raw(row),
+            });
+        }
+        for piece in std::iter::once(&Piece::Token(*token)).chain(&pieces) {
+            self.place_piece(col, piece);
+        }
+    }
 }
 
 #[cfg(test)]
 mod mut_board_tests {
     use super::*;
     use crate::basic::{column, row};
-    use crate::board::array_board::ArrayBoard;
+    use crate::board::{array_board::ArrayBoard, bit_board::BitBoard};
 
     #[test]
     fn test() {
         won_at_horizontal::<ArrayBoard>();
     }
 
+    #[test]
+    fn count_adjacent_connect_three() {
+        let board = BitBoard::<4, 3, 3>::read(
+            "|....|
+             |....|
+             |YRRY|",
+        );
+        let cell = Cell {
+            col: column::Idx::raw(2),
+            row: row::Idx::BOTTOM,
+        };
+        // a pair is one short of three, and each lone direction two short
+        assert_eq!(board.count_adjacent_at(&cell), Some((1, 3)));
+
+        let board = BitBoard::<4, 3, 3>::read(
+            "|....|
+             |YY..|
+             |RRRY|",
+        );
+        assert_eq!(board.count_adjacent_at(&cell), None);
+        assert!(board.won_at(&cell));
+    }
+
     fn won_at_horizontal<B: MutBoard>() {
         let mut board = B::EMPTY;
 
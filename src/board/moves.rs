@@ -1,29 +1,70 @@
 use crate::basic::*;
 use crate::board::{Board, CloneBoard, MutBoard};
+use std::hash::{Hash, Hasher};
 
-/// Moves implementation using a vector of placed tokens.
-/// Stores only the moves made, reconstructing the board state as needed.
+/// Moves implementation using a vector of placed pieces.
+/// Stores only the moves made, and any blockers set up, in the order they were placed,
+/// reconstructing the board state as needed.
 /// The worst representation I could think of.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Moves {
-    pub moves: Vec<(column::Idx, Token)>,
+#[derive(Debug, Clone)]
+pub struct Moves<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+> {
+    pub moves: Vec<(column::Idx<W>, Piece)>,
 }
 
-impl Moves {
-    fn count_in_column(&self, col: &column::Idx) -> usize {
+impl<const W: usize, const H: usize, const K: usize> Moves<W, H, K> {
+    fn count_in_column(&self, col: &column::Idx<W>) -> usize {
         self.moves.iter().filter(|(c, _)| c == col).count()
     }
+
+    /// Reconstructs the piece in each cell, column by column.
+    fn grid(&self) -> [[Option<Piece>; H]; W] {
+        let mut grid = [[None; H]; W];
+        let mut heights = [0; W];
+        for (col, piece) in &self.moves {
+            let col = usize::from(*col);
+            grid[col][heights[col]] = Some(*piece);
+            heights[col] += 1;
+        }
+        grid
+    }
+}
+
+/// Moves are equal if they make the same position, whatever order they were played in,
+/// as with the other boards.
+impl<const W: usize, const H: usize, const K: usize> PartialEq for Moves<W, H, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.grid() == other.grid()
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize> Eq for Moves<W, H, K> {}
+
+impl<const W: usize, const H: usize, const K: usize> Hash for Moves<W, H, K> {
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        self.grid().hash(state);
+    }
 }
 
-impl Board for Moves {
+impl<const W: usize, const H: usize, const K: usize> Board<W, H, K> for Moves<W, H, K> {
     const EMPTY: Self = Moves { moves: Vec::new() };
 
-    fn get(&self, cell: &Cell) -> Option<Token> {
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        match self.piece(cell)? {
+            Piece::Token(token) => Some(token),
+            Piece::Blocker => None,
+        }
+    }
+
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
         let mut col_count = 0;
-        for (col, token) in &self.moves {
+        for (col, piece) in &self.moves {
             if *col == cell.col {
                 if col_count == usize::from(cell.row) {
-                    return Some(*token);
+                    return Some(*piece);
                 }
                 col_count += 1;
             }
@@ -31,25 +72,30 @@ impl Board for Moves {
         None
     }
 
-    fn can_place(&self, col: &column::Idx) -> bool {
-        self.count_in_column(col) < row::COUNT
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
+        self.count_in_column(col) < H
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        self.place_piece(col, &Piece::Token(*token))
     }
 
-    fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell> {
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.place_piece(col, &Piece::Blocker)
+    }
+
+    fn place_piece(&mut self, col: &column::Idx<W>, piece: &Piece) -> Option<Cell<W, H>> {
         let row = self.count_in_column(col);
         let row = row::Idx::try_from(row).ok()?;
-        self.moves.push((*col, *token));
-        Some(Cell {
-            row: row::Idx::try_from(row).unwrap(),
-            col: *col,
-        })
+        self.moves.push((*col, *piece));
+        Some(Cell { row, col: *col })
     }
 }
 
-impl CloneBoard for Moves {}
+impl<const W: usize, const H: usize, const K: usize> CloneBoard<W, H, K> for Moves<W, H, K> {}
 
-impl MutBoard for Moves {
-    fn unplace(&mut self, cell: &Cell) {
+impl<const W: usize, const H: usize, const K: usize> MutBoard<W, H, K> for Moves<W, H, K> {
+    fn unplace(&mut self, cell: &Cell<W, H>) {
         let mut col_count = 0;
         for (i, (col, _)) in self.moves.iter().enumerate() {
             if *col == cell.col {
@@ -62,3 +108,12 @@ impl MutBoard for Moves {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    make_board_tests!(Moves);
+    make_mut_board_tests!(Moves);
+    make_blocker_tests!(Moves);
+}
@@ -0,0 +1,193 @@
+use crate::basic::*;
+use crate::board::{Board, CloneBoard, MutBoard};
+use crate::lines;
+use crate::patterns::{Placements, WinPatterns};
+use std::marker::PhantomData;
+
+/// Any board won by completing one of the patterns of `P` instead of a line of `K`.
+/// Every solver and evaluator reads the ways to win from `Board::win_masks`,
+/// so wrapping a board is enough to play it with other patterns.
+/// Patterns that are not the same in the mirror image cannot wrap a board that compares
+/// equal to its mirror image, such as `SymmBoard`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Patterned<B, P>(B, PhantomData<P>);
+
+impl<B, P: WinPatterns> Patterned<B, P> {
+    /// Wraps the board, to be won with the patterns of `P`.
+    /// Panics if the board compares equal to its mirror image and the patterns are not
+    /// the same in the mirror image.
+    pub fn new<const W: usize, const H: usize, const K: usize>(board: B) -> Self
+    where
+        B: Board<W, H, K>,
+    {
+        assert!(!B::MIRROR_EQ || P::SYMMETRIC, "{ASYMMETRIC}");
+        Patterned(board, PhantomData)
+    }
+
+    /// The wrapped board.
+    pub fn board(&self) -> &B {
+        &self.0
+    }
+}
+
+const ASYMMETRIC: &str = "a board equal to its mirror image needs mirror-symmetric patterns";
+
+impl<const W: usize, const H: usize, const K: usize, B, P> Board<W, H, K> for Patterned<B, P>
+where
+    B: Board<W, H, K>,
+    P: WinPatterns + std::fmt::Debug + Eq,
+{
+    const EMPTY: Self = {
+        assert!(!B::MIRROR_EQ || P::SYMMETRIC, "{}", ASYMMETRIC);
+        Patterned(B::EMPTY, PhantomData)
+    };
+
+    const CYLINDER: bool = B::CYLINDER;
+
+    const MISERE: bool = B::MISERE;
+
+    const MIRROR_EQ: bool = B::MIRROR_EQ;
+
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        self.0.get(cell)
+    }
+
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        self.0.piece(cell)
+    }
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
+        self.0.can_place(col)
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        self.0.place(col, token)
+    }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.0.block(col)
+    }
+
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        self.0.next_cell(col)
+    }
+
+    fn win_masks() -> &'static [u128] {
+        Placements::<P, W, H>::masks(Self::CYLINDER)
+    }
+
+    fn mirror_symmetric() -> bool {
+        P::SYMMETRIC
+    }
+
+    fn would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool {
+        let cell = lines::cell_mask(cell);
+        let own = lines::token_mask(self, *token) | cell;
+        Self::win_masks()
+            .iter()
+            .any(|mask| mask & cell != 0 && mask & own == *mask)
+    }
+
+    fn won_at(&self, cell: &Cell<W, H>) -> bool {
+        let Some(token) = self.get(cell) else {
+            panic!("Tried to check for a win at an empty cell.")
+        };
+        self.would_win_at(cell, &token)
+    }
+
+    /// Counts the patterns through the given cell, still open to its token,
+    /// that are one or two tokens short of being completed.
+    /// Returns None if one is completed.
+    fn count_adjacent_at(&self, cell: &Cell<W, H>) -> Option<(usize, usize)> {
+        let Some(token) = self.get(cell) else {
+            panic!("Tried to count adjacent at an empty cell")
+        };
+        let cell = lines::cell_mask(cell);
+        let own = lines::token_mask(self, token);
+        let mut counts = (0, 0);
+
+        for mask in lines::open_lines(self, token).filter(|mask| mask & cell != 0) {
+            match (mask & !own).count_ones() {
+                0 => return None,
+                1 => counts.0 += 1,
+                2 => counts.1 += 1,
+                _ => (),
+            }
+        }
+        Some(counts)
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, B, P> CloneBoard<W, H, K> for Patterned<B, P>
+where
+    B: CloneBoard<W, H, K>,
+    P: WinPatterns + Clone + std::fmt::Debug + Eq,
+{
+    fn flipped(&self) -> Self {
+        Patterned(self.0.flipped(), PhantomData)
+    }
+
+    fn rotated(&self, by: usize) -> Self {
+        Patterned(self.0.rotated(by), PhantomData)
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, B, P> MutBoard<W, H, K> for Patterned<B, P>
+where
+    B: MutBoard<W, H, K>,
+    P: WinPatterns + std::fmt::Debug + Eq,
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
+        self.0.unplace(cell)
+    }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        self.0.pop(col)
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        self.0.unpop(col, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::bit_board::BitBoard;
+    use crate::board::symm_board::SymmBoard;
+
+    /// Straight lines of four, the usual way to win.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Fours;
+
+    impl WinPatterns for Fours {
+        const PATTERNS: &'static str = "#### #.../.#../..#./...#";
+        const ORIENTATIONS: bool = true;
+    }
+
+    make_board_tests!(Patterned<BitBoard, Fours>);
+    make_mut_board_tests!(Patterned<BitBoard, Fours>);
+    make_cylinder_tests!(Patterned<BitBoard<7, 6, 4, true>, Fours>);
+    make_blocker_tests!(Patterned<BitBoard, Fours>);
+
+    /// An L, which is not its own mirror image.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Ell;
+
+    impl WinPatterns for Ell {
+        const PATTERNS: &'static str = "#./##";
+    }
+
+    #[test]
+    fn wraps_symmetric_boards() {
+        let board = Patterned::<_, Fours>::new(SymmBoard::<4, 4, 4>::EMPTY);
+        assert_eq!(board, Patterned::EMPTY);
+        Patterned::<_, Ell>::new(BitBoard::<4, 4, 4>::EMPTY);
+    }
+
+    #[test]
+    #[should_panic(expected = "mirror-symmetric patterns")]
+    fn refuses_asymmetric_patterns_on_symmetric_boards() {
+        Patterned::<_, Ell>::new(SymmBoard::<4, 4, 4>::EMPTY);
+    }
+}
@@ -4,26 +4,64 @@ use std::hash::Hash;
 
 /// A board implementation using bit manipulation for storage with
 /// customised equality and hashing for symmetry.
-/// Each column is stored as a BitCol.
+/// Each column is stored as a BitCol, so the board is at most 7 rows high.
+/// Lines wrap round from the last column into the first if `C`, as on a cylinder,
+/// and then every rotation of a board is equal to it too.
+/// Blockers never move, so a board only equals an image of it with its blockers in the
+/// same cells, and the symmetry is lost on a board with asymmetrical blockers.
 #[derive(Clone, Debug)]
-pub struct SymmBoard {
-    cols: [bit_col::BitCol; column::COUNT],
+pub struct SymmBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+    const C: bool = false,
+> {
+    cols: [bit_col::BitCol<H>; W],
 }
-impl Board for SymmBoard {
-    const EMPTY: Self = SymmBoard {
-        cols: [bit_col::BitCol::EMPTY; column::COUNT],
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> SymmBoard<W, H, K, C> {
+    const FITS: () = {
+        assert!(H <= 7, "a column does not fit in a BitCol");
+        assert!(
+            !C || K <= W,
+            "a line cannot be longer than the way round the cylinder"
+        );
+    };
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Board<W, H, K>
+    for SymmBoard<W, H, K, C>
+{
+    const EMPTY: Self = {
+        let () = Self::FITS;
+        SymmBoard {
+            cols: [bit_col::BitCol::EMPTY; W],
+        }
     };
 
-    fn get(&self, cell: &Cell) -> Option<Token> {
+    const CYLINDER: bool = C;
+
+    const MIRROR_EQ: bool = true;
+
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
         self.cols[usize::from(cell.col)].get(&cell.row)
     }
 
-    fn can_place(&self, col: &column::Idx) -> bool {
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        self.cols[usize::from(cell.col)].piece(&cell.row)
+    }
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
         !self.cols[usize::from(*col)].is_full()
     }
 
-    fn place(&mut self, col: &column::Idx, token: &Token) -> Option<Cell> {
-        if self.can_place(col) == false {
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let row = row::Idx::try_from(self.cols[usize::from(*col)].count()).ok()?;
+        Some(Cell { col: *col, row })
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        if !self.can_place(col) {
             return None;
         }
 
@@ -34,37 +72,92 @@ impl Board for SymmBoard {
             row: row::Idx::try_from(self.cols[col_idx].count() - 1).unwrap(),
         })
     }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let cell = self.next_cell(col)?;
+        self.cols[usize::from(*col)].force_block();
+        Some(cell)
+    }
 }
 
-impl CloneBoard for SymmBoard {}
+impl<const W: usize, const H: usize, const K: usize, const C: bool> CloneBoard<W, H, K>
+    for SymmBoard<W, H, K, C>
+{
+    fn flipped(&self) -> Self {
+        let mut cols = self.cols;
+        cols.reverse();
+        SymmBoard { cols }
+    }
 
-impl MutBoard for SymmBoard {
-    fn unplace(&mut self, cell: &Cell) {
+    fn rotated(&self, by: usize) -> Self {
+        let mut cols = self.cols;
+        cols.rotate_right(by % W);
+        SymmBoard { cols }
+    }
+
+    fn canonical(&self) -> Self {
+        SymmBoard {
+            cols: bit_col::canonical(self.cols, C),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> MutBoard<W, H, K>
+    for SymmBoard<W, H, K, C>
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
         self.cols[usize::from(cell.col)].force_pop();
     }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        let col = &mut self.cols[usize::from(*col)];
+        (!col.is_empty() && !col.is_blocked(&row::Idx::BOTTOM)).then(|| col.force_pop_bottom())
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        self.cols[usize::from(*col)].force_push_bottom(token);
+    }
 }
 
-impl PartialEq for SymmBoard {
+impl<const W: usize, const H: usize, const K: usize, const C: bool> PartialEq
+    for SymmBoard<W, H, K, C>
+{
     fn eq(&self, other: &Self) -> bool {
-        self.cols == other.cols
-            || self
-                .cols
+        if C {
+            return bit_col::canonical(self.cols, C) == bit_col::canonical(other.cols, C);
+        }
+        let same_blockers = || {
+            self.cols
                 .iter()
-                .zip(other.cols.iter().rev())
-                .all(|(a, b)| a == b)
+                .zip(other.cols.iter())
+                .all(|(a, b)| a.blockers() == b.blockers())
+        };
+        self.cols == other.cols
+            || (same_blockers()
+                && self
+                    .cols
+                    .iter()
+                    .zip(other.cols.iter().rev())
+                    .all(|(a, b)| a == b))
     }
 }
 
-impl Eq for SymmBoard {}
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Eq for SymmBoard<W, H, K, C> {}
 
-impl Hash for SymmBoard {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.cols[3].hash(state);
-        for i in 0..3 {
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Hash for SymmBoard<W, H, K, C> {
+    fn hash<S: std::hash::Hasher>(&self, state: &mut S) {
+        if C {
+            return bit_col::canonical(self.cols, C).hash(state);
+        }
+        if W % 2 == 1 {
+            self.cols[W / 2].hash(state);
+        }
+        for i in 0..W / 2 {
             let col_a = self.cols[i];
-            let col_b = self.cols[6 - i];
+            let col_b = self.cols[W - 1 - i];
             state.write_u8(col_a.as_u8() | col_b.as_u8());
             state.write_u8(col_a.as_u8() & col_b.as_u8());
+            state.write_u8(col_a.blockers() | col_b.blockers());
         }
     }
 }
@@ -78,11 +171,13 @@ mod tests {
 
     make_board_tests!(SymmBoard);
     make_mut_board_tests!(SymmBoard);
+    make_blocker_tests!(SymmBoard);
+    make_cylinder_tests!(SymmBoard<7, 6, 4, true>);
 
     #[test]
     fn test_symmetry() {
-        let mut board_a = SymmBoard::EMPTY;
-        let mut board_b = SymmBoard::EMPTY;
+        let mut board_a = <SymmBoard>::EMPTY;
+        let mut board_b = <SymmBoard>::EMPTY;
         let mut token = Token::START;
         for _ in row::BOTTOM_UP {
             for col in column::IDXS {
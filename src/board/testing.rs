@@ -1,5 +1,5 @@
-use crate::basic::{Cell, Token, column, row};
-use crate::board::{Board, MutBoard};
+use crate::basic::{Cell, Piece, Token, column, row};
+use crate::board::{Board, CloneBoard, MutBoard};
 
 macro_rules! make_test {
     ($b:ty, $mod:ident, $func:ident) => {
@@ -15,12 +15,29 @@ macro_rules! make_board_tests {
         make_test!($b, board_tests, empty_is_empty);
         make_test!($b, board_tests, cannot_place_in_full_column);
         make_test!($b, board_tests, won_at_basic);
+        make_test!($b, board_tests, next_cell_matches_place);
     };
 }
 
 macro_rules! make_mut_board_tests {
     ($b:ty) => {
         make_test!($b, mut_board_tests, place_unplace_eq);
+        make_test!($b, mut_board_tests, pop_unpop_eq);
+    };
+}
+
+macro_rules! make_blocker_tests {
+    ($b:ty) => {
+        make_test!($b, blocker_tests, blockers_stack_and_break_lines);
+        make_test!($b, blocker_tests, blockers_stay_in_their_cells);
+        make_test!($b, blocker_tests, blockers_do_not_pop);
+    };
+}
+
+macro_rules! make_cylinder_tests {
+    ($b:ty) => {
+        make_test!($b, cylinder_tests, lines_wrap_around);
+        make_test!($b, cylinder_tests, rotations_are_canonical);
     };
 }
 
@@ -85,6 +102,37 @@ pub mod board_tests {
             "`{name}::won_at returned false on a winning cell."
         );
     }
+
+    pub fn next_cell_matches_place<B: Board>(name: &str) {
+        let mut board = B::read(
+            "|       |
+             |       |
+             |       |
+             |RRR    |
+             |YYY    |",
+        );
+        let cell = Cell {
+            col: column::Idx::raw(3),
+            row: row::Idx::raw(0),
+        };
+        assert!(
+            board.would_win_at(&cell, &Token::Yellow),
+            "`{name}::would_win_at` returned false on a winning cell."
+        );
+        assert!(
+            !board.would_win_at(&cell, &Token::Red),
+            "`{name}::would_win_at` returned true on a non-winning cell."
+        );
+
+        for col in column::IDXS {
+            let expected = board.next_cell(&col);
+            assert_eq!(
+                expected,
+                board.place(&col, &Token::Yellow),
+                "`{name}::next_cell` disagrees with `{name}::place`."
+            );
+        }
+    }
 }
 
 pub mod mut_board_tests {
@@ -93,7 +141,7 @@ pub mod mut_board_tests {
     pub fn place_unplace_eq<B: Clone + MutBoard>(name: &str) {
         let mut board = B::EMPTY;
         let mut token = Token::START;
-        for _ in 0..row::Idx::COUNT {
+        for _ in 0..row::COUNT {
             for col in column::IDXS {
                 let temp = board.clone();
 
@@ -107,4 +155,214 @@ pub mod mut_board_tests {
             }
         }
     }
+
+    pub fn pop_unpop_eq<B: Clone + MutBoard>(name: &str) {
+        let column = |board: &B, col| -> Vec<Option<Token>> {
+            row::BOTTOM_UP
+                .map(|row| board.get(&Cell { col, row }))
+                .collect()
+        };
+        let mut board = B::EMPTY;
+        let mut token = Token::START;
+        for col in column::IDXS {
+            // from one token in the first column up to a full column
+            for _ in 0..row::COUNT.min(usize::from(col) + 1) {
+                board.place(&col, &token);
+                token = token.next();
+            }
+        }
+
+        for col in column::IDXS {
+            let temp = board.clone();
+            let before = column(&board, col);
+            assert_eq!(
+                board.pop(&col),
+                before[0],
+                "`{name}::pop` returned the wrong token."
+            );
+            let mut after = before[1..].to_vec();
+            after.push(None);
+            assert_eq!(
+                column(&board, col),
+                after,
+                "`{name}::pop` did not shift the column down."
+            );
+
+            board.unpop(&col, &before[0].unwrap());
+            assert_eq!(board, temp, "`{name}::unpop∘{name}::pop` != id.");
+        }
+        let mut empty = B::EMPTY;
+        assert_eq!(
+            empty.pop(&column::Idx::ZERO),
+            None,
+            "`{name}::pop` popped from an empty column."
+        );
+    }
+}
+
+pub mod cylinder_tests {
+    use super::*;
+
+    pub fn lines_wrap_around<B: Board>(name: &str) {
+        assert!(B::CYLINDER, "`{name}` is not a cylinder.");
+        let mut board = B::read(
+            "|RR....R|
+             |YY....Y|",
+        );
+        let col = column::Idx::raw(5);
+        let cell = board.next_cell(&col).unwrap();
+        assert!(
+            board.would_win_at(&cell, &Token::Yellow),
+            "`{name}::would_win_at` returned false on a row round the cylinder."
+        );
+        board.place(&col, &Token::Yellow);
+        assert!(
+            board.won_at(&cell),
+            "`{name}::won_at` returned false on a row round the cylinder."
+        );
+        assert_eq!(board.count_adjacent_at(&cell), None);
+
+        // up to the right from col 5, on round into col 0
+        let board = B::read(
+            "|YR.....|
+             |RY....Y|
+             |YR...YR|",
+        );
+        let cell = board.next_cell(&column::Idx::raw(1)).unwrap();
+        assert!(
+            board.would_win_at(&cell, &Token::Yellow),
+            "`{name}::would_win_at` returned false on a diagonal round the cylinder."
+        );
+    }
+
+    pub fn rotations_are_canonical<B: CloneBoard>(name: &str) {
+        let board = B::read(
+            "|R......|
+             |YR.....|
+             |RYY..RY|",
+        );
+        for by in 0..column::COUNT {
+            let rotated = board.rotated(by);
+            assert_eq!(
+                rotated.rotated(column::COUNT - by),
+                board,
+                "`{name}::rotated` did not come back round."
+            );
+            assert_eq!(
+                rotated.canonical(),
+                board.canonical(),
+                "`{name}::canonical` differs between rotations."
+            );
+            assert_eq!(
+                rotated.flipped().canonical(),
+                board.canonical(),
+                "`{name}::canonical` differs between reflections."
+            );
+        }
+
+        let other = B::read(
+            "|R......|
+             |YR.....|
+             |RYY.R.Y|",
+        );
+        assert_ne!(
+            other.canonical(),
+            board.canonical(),
+            "`{name}::canonical` is the same for different boards."
+        );
+    }
+}
+
+pub mod blocker_tests {
+    use super::*;
+
+    pub fn blockers_stack_and_break_lines<B: Board>(name: &str) {
+        let mut board = B::read(
+            "|RR.#...|
+             |YYY#...|",
+        );
+        let blocker = Cell {
+            col: column::Idx::raw(3),
+            row: row::Idx::BOTTOM,
+        };
+        assert_eq!(
+            board.piece(&blocker),
+            Some(Piece::Blocker),
+            "`{name}::read` did not place a blocker."
+        );
+        assert_eq!(
+            board.get(&blocker),
+            None,
+            "`{name}::get` returned a blocker."
+        );
+        assert_eq!(
+            board.curr_player(),
+            Token::Red,
+            "`{name}::curr_player` counted a blocker."
+        );
+
+        let col = column::Idx::raw(3);
+        let cell = board.place(&col, &Token::Red).unwrap();
+        assert_eq!(
+            cell.row,
+            row::Idx::raw(2),
+            "`{name}::place` did not stack on the blockers."
+        );
+        let cell = board.place(&column::Idx::raw(2), &Token::Yellow).unwrap();
+        assert!(
+            !board.won_at(&cell),
+            "`{name}::won_at` counted a line through a blocker."
+        );
+    }
+
+    pub fn blockers_stay_in_their_cells<B: CloneBoard>(name: &str) {
+        let board = B::read("|Y.....#|");
+        assert_eq!(
+            board.flipped().piece(&Cell {
+                col: column::Idx::ZERO,
+                row: row::Idx::BOTTOM,
+            }),
+            Some(Piece::Blocker),
+            "`{name}::flipped` did not move the blocker."
+        );
+        assert_eq!(
+            board.canonical(),
+            board,
+            "`{name}::canonical` moved the blocker."
+        );
+
+        // a mirror image with the blockers in the same cells is equivalent
+        let board = B::read("|#Y....#|");
+        assert_eq!(
+            board.flipped().canonical(),
+            board.canonical(),
+            "`{name}::canonical` differs between reflections."
+        );
+    }
+
+    pub fn blockers_do_not_pop<B: Clone + MutBoard>(name: &str) {
+        let mut board = B::read(
+            "|#......|
+             |Y#.....|",
+        );
+        let temp = board.clone();
+        assert_eq!(
+            board.pop(&column::Idx::raw(1)),
+            None,
+            "`{name}::pop` popped a blocker."
+        );
+        assert_eq!(board, temp, "`{name}::pop` changed a blocked column.");
+
+        assert_eq!(board.pop(&column::Idx::ZERO), Some(Token::Yellow));
+        assert_eq!(
+            board.piece(&Cell {
+                col: column::Idx::ZERO,
+                row: row::Idx::BOTTOM,
+            }),
+            Some(Piece::Blocker),
+            "`{name}::pop` did not move the blocker down."
+        );
+        board.unpop(&column::Idx::ZERO, &Token::Yellow);
+        assert_eq!(board, temp, "`{name}::unpop∘{name}::pop` != id.");
+    }
 }
@@ -0,0 +1,328 @@
+use crate::basic::*;
+use crate::board::{Board, CloneBoard, MutBoard};
+
+/// A board implementation storing each player's tokens as the bits of a u128,
+/// for boards up to 10x10 or any size with `W * (H + 1) <= 128`.
+///
+/// Each column takes `H + 1` bits, from the bottom row in the lowest bit, with an extra
+/// always-empty bit above the top row. The empty bits keep a line from running
+/// over the top of one column into the bottom of the next, so lines can be found
+/// by shifting the whole board at once.
+///
+/// Lines wrap round from the last column into the first if `C`, as on a cylinder,
+/// found by rotating the bits of the whole board instead.
+///
+/// A blocker sets its bit in both `yellow` and `red`, which a token never does.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WideBitBoard<
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+    const C: bool = false,
+> {
+    yellow: u128,
+    red: u128,
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> WideBitBoard<W, H, K, C> {
+    const FITS: () = {
+        assert!(W * (H + 1) <= 128, "the board does not fit in a u128");
+        assert!(
+            !C || K <= W,
+            "a line cannot be longer than the way round the cylinder"
+        );
+    };
+
+    /// The number of bits used, `H + 1` for each column.
+    const BITS: usize = W * (H + 1);
+
+    /// Every bit used.
+    const ALL: u128 = if Self::BITS == 128 {
+        u128::MAX
+    } else {
+        (1 << Self::BITS) - 1
+    };
+
+    /// The bits of a single column, shifted to the bottom.
+    const COLUMN: u128 = (1 << H) - 1;
+
+    /// The shift moving a bit one cell right, down, to bottom-right and to bottom-left.
+    const SHIFTS: [usize; 4] = [H + 1, 1, H, H + 2];
+
+    fn bit(cell: &Cell<W, H>) -> u128 {
+        1 << (usize::from(cell.col) * (H + 1) + usize::from(cell.row))
+    }
+
+    fn offset(col: &column::Idx<W>) -> usize {
+        usize::from(*col) * (H + 1)
+    }
+
+    fn height(&self, col: &column::Idx<W>) -> usize {
+        (((self.yellow | self.red) >> Self::offset(col)) & Self::COLUMN).count_ones() as usize
+    }
+
+    fn tokens(&self, token: &Token) -> u128 {
+        match token {
+            Token::Yellow => self.yellow & !self.red,
+            Token::Red => self.red & !self.yellow,
+        }
+    }
+
+    fn blockers(&self) -> u128 {
+        self.yellow & self.red
+    }
+
+    fn tokens_mut(&mut self, token: &Token) -> &mut u128 {
+        match token {
+            Token::Yellow => &mut self.yellow,
+            Token::Red => &mut self.red,
+        }
+    }
+
+    /// Moves every bit `by` places up, into later columns.
+    /// On a cylinder, bits moved past the last column come round into the first.
+    fn shift_up(bits: u128, by: usize) -> u128 {
+        if !C {
+            return bits << by;
+        }
+        match by % Self::BITS {
+            0 => bits,
+            by => (bits << by | bits >> (Self::BITS - by)) & Self::ALL,
+        }
+    }
+
+    /// Moves every bit `by` places down, into earlier columns, as `shift_up` does.
+    fn shift_down(bits: u128, by: usize) -> u128 {
+        if !C {
+            return bits >> by;
+        }
+        Self::shift_up(bits, Self::BITS - by % Self::BITS)
+    }
+
+    /// Returns true if a line of `K` of the given bits runs through the bit at `at`.
+    fn line_through(bits: u128, at: u128) -> bool {
+        Self::SHIFTS.iter().any(|&shift| {
+            // the lowest bit of each run of K, then every bit of those runs
+            let starts = (1..K).fold(bits, |starts, i| starts & Self::shift_down(bits, shift * i));
+            let runs = (1..K).fold(starts, |runs, i| runs | Self::shift_up(starts, shift * i));
+            runs & at != 0
+        })
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> Board<W, H, K>
+    for WideBitBoard<W, H, K, C>
+{
+    const EMPTY: Self = {
+        let () = Self::FITS;
+        WideBitBoard { yellow: 0, red: 0 }
+    };
+
+    const CYLINDER: bool = C;
+
+    fn get(&self, cell: &Cell<W, H>) -> Option<Token> {
+        match self.piece(cell) {
+            Some(Piece::Token(token)) => Some(token),
+            _ => None,
+        }
+    }
+
+    fn piece(&self, cell: &Cell<W, H>) -> Option<Piece> {
+        let bit = Self::bit(cell);
+        match (self.yellow & bit != 0, self.red & bit != 0) {
+            (true, true) => Some(Piece::Blocker),
+            (true, false) => Some(Piece::Token(Token::Yellow)),
+            (false, true) => Some(Piece::Token(Token::Red)),
+            (false, false) => None,
+        }
+    }
+
+    fn can_place(&self, col: &column::Idx<W>) -> bool {
+        self.height(col) < H
+    }
+
+    fn next_cell(&self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let row = row::Idx::try_from(self.height(col)).ok()?;
+        Some(Cell { col: *col, row })
+    }
+
+    fn place(&mut self, col: &column::Idx<W>, token: &Token) -> Option<Cell<W, H>> {
+        let cell = self.next_cell(col)?;
+        *self.tokens_mut(token) |= Self::bit(&cell);
+        Some(cell)
+    }
+
+    fn block(&mut self, col: &column::Idx<W>) -> Option<Cell<W, H>> {
+        let cell = self.next_cell(col)?;
+        self.yellow |= Self::bit(&cell);
+        self.red |= Self::bit(&cell);
+        Some(cell)
+    }
+
+    fn would_win_at(&self, cell: &Cell<W, H>, token: &Token) -> bool {
+        let bit = Self::bit(cell);
+        Self::line_through(self.tokens(token) | bit, bit)
+    }
+
+    fn won_at(&self, cell: &Cell<W, H>) -> bool {
+        let Some(token) = self.get(cell) else {
+            panic!("Tried to check for a win at an empty cell.")
+        };
+        Self::line_through(self.tokens(&token), Self::bit(cell))
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> CloneBoard<W, H, K>
+    for WideBitBoard<W, H, K, C>
+{
+    fn flipped(&self) -> Self {
+        let mut board = Self::EMPTY;
+        for col in column::Idx::<W>::ALL {
+            let (from, to) = (Self::offset(&col), Self::offset(&col.flipped()));
+            board.yellow |= (self.yellow >> from & Self::COLUMN) << to;
+            board.red |= (self.red >> from & Self::COLUMN) << to;
+        }
+        board
+    }
+
+    fn rotated(&self, by: usize) -> Self {
+        let by = by % W * (H + 1);
+        WideBitBoard {
+            yellow: Self::shift_up(self.yellow, by),
+            red: Self::shift_up(self.red, by),
+        }
+    }
+
+    fn canonical(&self) -> Self {
+        let turns = if C { W } else { 1 };
+        [self.clone(), self.flipped()]
+            .iter()
+            .flat_map(|board| (0..turns).map(|by| board.rotated(by)))
+            .filter(|board| board.blockers() == self.blockers())
+            .min_by_key(|board| (board.yellow, board.red))
+            .expect("there is at least the board itself")
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, const C: bool> MutBoard<W, H, K>
+    for WideBitBoard<W, H, K, C>
+{
+    fn unplace(&mut self, cell: &Cell<W, H>) {
+        let bit = Self::bit(cell);
+        self.yellow &= !bit;
+        self.red &= !bit;
+    }
+
+    fn pop(&mut self, col: &column::Idx<W>) -> Option<Token> {
+        let bottom = self.get(&Cell {
+            col: *col,
+            row: row::Idx::BOTTOM,
+        })?;
+        let offset = Self::offset(col);
+        for bits in [&mut self.yellow, &mut self.red] {
+            let column = (*bits >> offset) & Self::COLUMN;
+            *bits = (*bits & !(Self::COLUMN << offset)) | ((column >> 1) << offset);
+        }
+        Some(bottom)
+    }
+
+    fn unpop(&mut self, col: &column::Idx<W>, token: &Token) {
+        let offset = Self::offset(col);
+        for bits in [&mut self.yellow, &mut self.red] {
+            let column = (*bits >> offset) & Self::COLUMN;
+            *bits =
+                (*bits & !(Self::COLUMN << offset)) | (((column << 1) & Self::COLUMN) << offset);
+        }
+        *self.tokens_mut(token) |= 1 << offset;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_symm::minimax_symm;
+    use crate::board::array_board::ArrayBoard;
+    use crate::board::differential::Harness;
+
+    make_board_tests!(WideBitBoard);
+    make_mut_board_tests!(WideBitBoard);
+    make_blocker_tests!(WideBitBoard);
+    make_cylinder_tests!(WideBitBoard<7, 6, 4, true>);
+
+    /// Plays random games on both boards through the differential harness.
+    fn agrees_with_array_board<const W: usize, const H: usize, const K: usize, const C: bool>(
+        games: usize,
+    ) {
+        let mut harness = Harness::<W, H, K>::new()
+            .with::<ArrayBoard<W, H, K, C>>("ArrayBoard")
+            .with::<WideBitBoard<W, H, K, C>>("WideBitBoard");
+        if let Err(divergence) = harness.run(0, games) {
+            panic!("{divergence}");
+        }
+    }
+
+    #[test]
+    fn agrees_on_standard_board() {
+        agrees_with_array_board::<7, 6, 4, false>(50);
+    }
+
+    #[test]
+    fn agrees_on_large_boards() {
+        agrees_with_array_board::<8, 7, 4, false>(50);
+        agrees_with_array_board::<9, 7, 4, false>(50);
+        agrees_with_array_board::<10, 10, 5, false>(20);
+    }
+
+    #[test]
+    fn agrees_on_small_boards() {
+        agrees_with_array_board::<4, 4, 3, false>(50);
+        agrees_with_array_board::<5, 4, 4, false>(50);
+        agrees_with_array_board::<3, 3, 3, false>(50);
+    }
+
+    #[test]
+    fn agrees_on_cylinders() {
+        agrees_with_array_board::<7, 6, 4, true>(50);
+        agrees_with_array_board::<5, 4, 4, true>(50);
+        agrees_with_array_board::<4, 4, 4, true>(50);
+        agrees_with_array_board::<3, 3, 3, true>(50);
+        // a full u128, so rotating has no unused bits to spare
+        agrees_with_array_board::<8, 15, 5, true>(20);
+    }
+
+    #[test]
+    fn lines_do_not_wrap_between_columns() {
+        // without the empty bit, the top of column 0 would run on into the bottom of column 1
+        let mut board = WideBitBoard::<10, 10, 4>::EMPTY;
+        let (first, second) = (column::Idx::raw(0), column::Idx::raw(1));
+        for _ in 0..7 {
+            board.place(&first, &Token::Yellow);
+        }
+        for _ in 0..3 {
+            board.place(&first, &Token::Red);
+        }
+        let top = board.place(&second, &Token::Red).unwrap();
+        assert!(!board.won_at(&top));
+        assert!(!board.won_at(&Cell {
+            col: first,
+            row: row::Idx::TOP,
+        }));
+        assert!(!board.would_win_at(&board.next_cell(&second).unwrap(), &Token::Red));
+    }
+
+    #[test]
+    fn solves_small_boards() {
+        assert_eq!(
+            minimax_symm(WideBitBoard::<4, 4, 4>::EMPTY, 16, Token::START),
+            None
+        );
+        assert_eq!(
+            minimax_symm(WideBitBoard::<4, 3, 3>::EMPTY, 12, Token::START),
+            Some(Token::Yellow)
+        );
+        assert_eq!(
+            minimax_symm(WideBitBoard::<4, 3, 3, true>::EMPTY, 12, Token::START),
+            Some(Token::Yellow)
+        );
+    }
+}
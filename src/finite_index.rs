@@ -1,34 +1,50 @@
-use std::{fmt::Display, range::Step};
+use std::{fmt::Display, iter::Step};
 
-/// Unsigned finite natural number type, with values in [0, N].
-/// Used for indexing a collection of N + 1 elements.
-#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord)]
+/// Unsigned finite natural number type, with values in [0, N).
+/// Used for indexing a collection of N elements.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, PartialOrd, Ord, Hash)]
 pub struct FiniteIndex<const N: usize>(usize);
 
 impl<const N: usize> FiniteIndex<N> {
     pub const ZERO: FiniteIndex<N> = FiniteIndex(0);
-    pub const MAX: FiniteIndex<N> = FiniteIndex(N);
-    pub const COUNT: usize = N + 1;
+    pub const MAX: FiniteIndex<N> = FiniteIndex(N - 1);
+    pub const COUNT: usize = N;
+
+    /// Every value, in increasing order.
+    pub const ALL: [Self; N] = {
+        let mut all = [FiniteIndex(0); N];
+        let mut i = 0;
+        while i < N {
+            all[i] = FiniteIndex(i);
+            i += 1;
+        }
+        all
+    };
 
     /// Creates a FiniteIndex from a raw usize value, panicking
     /// when out of bounds.
     pub const fn raw(value: usize) -> Self {
-        if value > N {
+        if value >= N {
             panic!("FiniteIndex out of bounds error: value exceeds the maximum allowed");
         }
         FiniteIndex(value)
     }
 
+    /// Returns the underlying value, usable in const contexts.
+    pub const fn get(&self) -> usize {
+        self.0
+    }
+
     /// Shifts the value by the given amount,
     /// staying within bounds by capping/saturating at the edges.
     pub fn shift(&self, by: isize) -> Self {
-        FiniteIndex(self.0.saturating_add_signed(by).clamp(0, N))
+        FiniteIndex(self.0.saturating_add_signed(by).min(N - 1))
     }
 
     /// Adds the given amount to the value,
     /// staying within bounds by capping at the maximum.
     pub fn add(&self, by: usize) -> Self {
-        FiniteIndex(self.0.saturating_add(by).min(N))
+        FiniteIndex(self.0.saturating_add(by).min(N - 1))
     }
 
     /// Subtracts the given amount from the value,
@@ -41,7 +57,7 @@ impl<const N: usize> FiniteIndex<N> {
     /// returning None when out of bounds
     pub fn try_shift(&self, by: isize) -> Option<Self> {
         let val = self.0.checked_add_signed(by)?;
-        if val <= N {
+        if val < N {
             Some(FiniteIndex(val))
         } else {
             None
@@ -59,11 +75,11 @@ impl<const N: usize> TryFrom<usize> for FiniteIndex<N> {
     type Error = String;
 
     fn try_from(value: usize) -> Result<Self, Self::Error> {
-        if value <= N {
+        if value < N {
             Ok(FiniteIndex(value))
         } else {
             Err(format!(
-                "FiniteIndex out of bounds error: {} > {}",
+                "FiniteIndex out of bounds error: {} >= {}",
                 value, N
             ))
         }
@@ -87,7 +103,7 @@ impl<const N: usize> Step for FiniteIndex<N> {
     }
 
     fn forward_checked(start: Self, count: usize) -> Option<Self> {
-        if (usize::from(start) + count) <= N {
+        if (usize::from(start) + count) < N {
             Some(start.add(count))
         } else {
             None
@@ -0,0 +1,422 @@
+use crate::basic::*;
+use crate::board::Board;
+
+/// The number of winning lines on the standard board.
+pub const COUNT: usize = 69;
+
+/// The number of cells on the standard board.
+pub const CELL_COUNT: usize = column::COUNT * row::COUNT;
+
+/// The most lines passing through a single cell of the standard board.
+pub const MAX_PER_CELL: usize = 13;
+
+/// The most winning lines on any board whose cells fit in a `u128` bitmask:
+/// at most one line starts in each direction from each cell.
+pub const MAX_COUNT: usize = 4 * u128::BITS as usize;
+
+/// Four cells in a straight line.
+pub type Line = [Cell; 4];
+
+/// Every winning line of the standard board:
+/// horizontal, then vertical, then up-right, then down-right diagonals.
+pub const LINES: [Line; COUNT] = make_lines();
+
+/// The bitmask form of each line in `LINES`.
+pub const LINE_MASKS: [u128; COUNT] = make_line_masks();
+
+/// For each cell index, the indices into `LINES` of the lines through that cell.
+/// Only the first `CELL_LINE_COUNTS[cell]` entries are valid.
+pub const CELL_LINES: [[u8; MAX_PER_CELL]; CELL_COUNT] = make_cell_lines().0;
+
+/// For each cell index, the number of lines through that cell.
+pub const CELL_LINE_COUNTS: [u8; CELL_COUNT] = make_cell_lines().1;
+
+/// For each cell index, a bitmask over `LINES` of the lines through that cell.
+pub const CELL_LINE_MASKS: [u128; CELL_COUNT] = make_cell_line_masks();
+
+/// The winning lines of a `W` by `H` board where `K` in a row wins,
+/// in the same order as `LINES`.
+pub struct Lines<const W: usize, const H: usize, const K: usize>;
+
+impl<const W: usize, const H: usize, const K: usize> Lines<W, H, K> {
+    const TABLE: ([u128; MAX_COUNT], usize) = make_masks(W, H, K, false);
+    const CYLINDER_TABLE: ([u128; MAX_COUNT], usize) = make_masks(W, H, K, true);
+
+    /// The number of winning lines.
+    pub const COUNT: usize = Self::TABLE.1;
+
+    /// The number of cells on the board.
+    pub const CELL_COUNT: usize = W * H;
+
+    /// The bitmask form of each winning line.
+    pub const MASKS: &'static [u128] = Self::TABLE.0.split_at(Self::TABLE.1).0;
+
+    /// The bitmask form of each winning line on a cylinder,
+    /// including those wrapping round from the last column into the first.
+    pub const CYLINDER_MASKS: &'static [u128] =
+        Self::CYLINDER_TABLE.0.split_at(Self::CYLINDER_TABLE.1).0;
+
+    /// Returns `CYLINDER_MASKS` on a cylinder, else `MASKS`.
+    pub const fn masks(cylinder: bool) -> &'static [u128] {
+        if cylinder {
+            Self::CYLINDER_MASKS
+        } else {
+            Self::MASKS
+        }
+    }
+}
+
+/// Returns the bit index of the given cell in bitmask forms,
+/// i.e. `col * H + row`.
+pub const fn cell_index<const W: usize, const H: usize>(cell: &Cell<W, H>) -> usize {
+    cell.col.get() * H + cell.row.get()
+}
+
+/// Returns the cell at the given bit index.
+pub const fn index_cell<const W: usize, const H: usize>(index: usize) -> Cell<W, H> {
+    Cell {
+        col: column::Idx::raw(index / H),
+        row: row::Idx::raw(index % H),
+    }
+}
+
+/// Returns the single-bit mask of the given cell.
+pub const fn cell_mask<const W: usize, const H: usize>(cell: &Cell<W, H>) -> u128 {
+    1 << cell_index(cell)
+}
+
+/// Returns the indices into `LINES` of the lines passing through the given cell.
+pub fn lines_through(cell: &Cell) -> &'static [u8] {
+    let index = cell_index(cell);
+    &CELL_LINES[index][..CELL_LINE_COUNTS[index] as usize]
+}
+
+/// Returns a bitmask of the cells holding the given piece.
+fn piece_mask<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    piece: Piece,
+) -> u128 {
+    let mut mask = 0;
+    for col in column::Idx::<W>::ALL {
+        for row in row::Idx::<H>::BOTTOM_UP {
+            let cell = Cell { col, row };
+            match board.piece(&cell) {
+                Some(p) if p == piece => mask |= cell_mask(&cell),
+                Some(_) => (),
+                None => break,
+            }
+        }
+    }
+    mask
+}
+
+/// Returns a bitmask of the cells holding the given token.
+pub fn token_mask<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    token: Token,
+) -> u128 {
+    piece_mask(board, Piece::Token(token))
+}
+
+/// Returns a bitmask of the cells holding blockers.
+pub fn blocker_mask<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+) -> u128 {
+    piece_mask(board, Piece::Blocker)
+}
+
+/// Returns the masks of the lines still open to `player`:
+/// those with no tokens of the opponent, and no blockers.
+/// The lines are the board's ways to win, as given by `Board::win_masks`.
+pub fn open_lines<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    player: Token,
+) -> impl Iterator<Item = u128> {
+    let closed = token_mask(board, player.next()) | blocker_mask(board);
+    B::win_masks()
+        .iter()
+        .copied()
+        .filter(move |mask| mask & closed == 0)
+}
+
+/// Returns true if `player` still has a line open to complete.
+pub fn can_still_win<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+    player: Token,
+) -> bool {
+    open_lines(board, player).next().is_some()
+}
+
+/// Returns true if neither player can complete any line, so the game is a draw.
+pub fn is_dead<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    board: &B,
+) -> bool {
+    !can_still_win(board, Token::Yellow) && !can_still_win(board, Token::Red)
+}
+
+/// The directions of the lines: horizontal, vertical, up-right and down-right.
+const DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Makes the masks of every line of `k` on a `w` by `h` board, with lines wrapping round
+/// from the last column into the first if `cylinder`.
+const fn make_masks(w: usize, h: usize, k: usize, cylinder: bool) -> ([u128; MAX_COUNT], usize) {
+    assert!(
+        w * h <= u128::BITS as usize,
+        "the board does not fit a u128"
+    );
+    let mut masks = [0; MAX_COUNT];
+    let mut n = 0;
+
+    let mut d = 0;
+    while d < DIRS.len() {
+        let (dc, dr) = DIRS[d];
+        let mut col = 0;
+        while col < w {
+            let mut row = 0;
+            while row < h {
+                let end_col = col as isize + (k as isize - 1) * dc;
+                let end_row = row as isize + (k as isize - 1) * dr;
+                let fits_cols = cylinder || (end_col >= 0 && end_col < w as isize);
+                if fits_cols && end_row >= 0 && end_row < h as isize {
+                    let mut mask = 0;
+                    let mut i = 0;
+                    while i < k as isize {
+                        let c = (col as isize + i * dc).rem_euclid(w as isize) as usize;
+                        let r = (row as isize + i * dr) as usize;
+                        mask |= 1 << (c * h + r);
+                        i += 1;
+                    }
+
+                    // a line all the way round a cylinder starts in every column
+                    let mut seen = false;
+                    let mut j = 0;
+                    while j < n {
+                        seen |= masks[j] == mask;
+                        j += 1;
+                    }
+                    if !seen {
+                        masks[n] = mask;
+                        n += 1;
+                    }
+                }
+                row += 1;
+            }
+            col += 1;
+        }
+        d += 1;
+    }
+    (masks, n)
+}
+
+const fn make_lines() -> [Line; COUNT] {
+    let origin = index_cell(0);
+    let mut lines = [[origin; 4]; COUNT];
+    let mut n = 0;
+
+    let mut d = 0;
+    while d < DIRS.len() {
+        let (dc, dr) = DIRS[d];
+        let mut col = 0;
+        while col < column::COUNT {
+            let mut row = 0;
+            while row < row::COUNT {
+                let end_col = col as isize + 3 * dc;
+                let end_row = row as isize + 3 * dr;
+                if end_col >= 0
+                    && end_col < column::COUNT as isize
+                    && end_row >= 0
+                    && end_row < row::COUNT as isize
+                {
+                    let mut k = 0;
+                    while k < 4 {
+                        let c = (col as isize + k * dc) as usize;
+                        let r = (row as isize + k * dr) as usize;
+                        lines[n][k as usize] = index_cell(c * row::COUNT + r);
+                        k += 1;
+                    }
+                    n += 1;
+                }
+                row += 1;
+            }
+            col += 1;
+        }
+        d += 1;
+    }
+
+    assert!(n == COUNT, "incorrect number of winning lines");
+    lines
+}
+
+const fn make_line_masks() -> [u128; COUNT] {
+    let mut masks = [0; COUNT];
+    let mut i = 0;
+    while i < COUNT {
+        let mut k = 0;
+        while k < 4 {
+            masks[i] |= cell_mask(&LINES[i][k]);
+            k += 1;
+        }
+        i += 1;
+    }
+    masks
+}
+
+const fn make_cell_lines() -> ([[u8; MAX_PER_CELL]; CELL_COUNT], [u8; CELL_COUNT]) {
+    let mut lines = [[0; MAX_PER_CELL]; CELL_COUNT];
+    let mut counts = [0; CELL_COUNT];
+    let mut i = 0;
+    while i < COUNT {
+        let mut k = 0;
+        while k < 4 {
+            let cell = cell_index(&LINES[i][k]);
+            lines[cell][counts[cell] as usize] = i as u8;
+            counts[cell] += 1;
+            k += 1;
+        }
+        i += 1;
+    }
+    (lines, counts)
+}
+
+const fn make_cell_line_masks() -> [u128; CELL_COUNT] {
+    let mut masks = [0; CELL_COUNT];
+    let mut cell = 0;
+    while cell < CELL_COUNT {
+        let mut k = 0;
+        while k < CELL_LINE_COUNTS[cell] as usize {
+            masks[cell] |= 1 << CELL_LINES[cell][k];
+            k += 1;
+        }
+        cell += 1;
+    }
+    masks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::bit_board::BitBoard;
+
+    #[test]
+    fn lines_are_distinct() {
+        for (i, mask) in LINE_MASKS.iter().enumerate() {
+            assert_eq!(mask.count_ones(), 4);
+            assert!(!LINE_MASKS[..i].contains(mask), "line {i} is a duplicate.");
+        }
+    }
+
+    #[test]
+    fn generic_lines_match() {
+        assert_eq!(Lines::<7, 6, 4>::MASKS, LINE_MASKS);
+        assert_eq!(Lines::<4, 4, 4>::COUNT, 10);
+        assert_eq!(Lines::<3, 3, 3>::COUNT, 8);
+        assert_eq!(Lines::<5, 4, 3>::COUNT, 3 * 4 + 5 * 2 + 3 * 2 * 2);
+        assert!(
+            Lines::<4, 3, 3>::MASKS
+                .iter()
+                .all(|mask| mask.count_ones() == 3)
+        );
+    }
+
+    #[test]
+    fn cylinder_lines_wrap() {
+        // every row and diagonal starts in every column, and rows all the way round only once
+        assert_eq!(
+            Lines::<5, 4, 3>::CYLINDER_MASKS.len(),
+            5 * 4 + 5 * 2 + 5 * 2 * 2
+        );
+        assert_eq!(Lines::<4, 4, 4>::CYLINDER_MASKS.len(), 4 + 4 + 4 * 2);
+        for (i, mask) in Lines::<4, 4, 4>::CYLINDER_MASKS.iter().enumerate() {
+            assert_eq!(mask.count_ones(), 4);
+            assert!(!Lines::<4, 4, 4>::CYLINDER_MASKS[..i].contains(mask));
+        }
+        assert!(
+            Lines::<5, 4, 3>::MASKS
+                .iter()
+                .all(|mask| Lines::<5, 4, 3>::CYLINDER_MASKS.contains(mask))
+        );
+
+        // dead on a flat board, but Yellow can still go round from col 3 to col 1
+        let board: BitBoard<4, 1, 3> = BitBoard::read("|Y.RY|");
+        assert!(is_dead(&board));
+        let board: BitBoard<4, 1, 3, true> = BitBoard::read("|Y.RY|");
+        assert!(!is_dead(&board));
+        assert!(!can_still_win(&board, Token::Red));
+    }
+
+    #[test]
+    fn blockers_close_lines() {
+        let board: BitBoard<4, 1, 3> = BitBoard::read("|...#|");
+        assert!(!is_dead(&board));
+        assert_eq!(blocker_mask(&board), 1 << 3);
+        let board: BitBoard<4, 1, 3> = BitBoard::read("|.#..|");
+        assert!(is_dead(&board));
+        assert_eq!(token_mask(&board, Token::Yellow), 0);
+    }
+
+    #[test]
+    fn cell_lines_match() {
+        for col in column::IDXS {
+            for row in row::BOTTOM_UP {
+                let cell = Cell { col, row };
+                let expected: Vec<u8> = (0..COUNT as u8)
+                    .filter(|&i| LINES[i as usize].contains(&cell))
+                    .collect();
+                assert_eq!(lines_through(&cell), expected);
+                assert_eq!(
+                    CELL_LINE_MASKS[cell_index(&cell)].count_ones() as usize,
+                    expected.len()
+                );
+            }
+        }
+
+        let corner = Cell {
+            col: column::Idx::ZERO,
+            row: row::Idx::BOTTOM,
+        };
+        assert_eq!(lines_through(&corner).len(), 3);
+        let centre = Cell {
+            col: column::Idx::CENTRE,
+            row: row::Idx::raw(2),
+        };
+        assert_eq!(lines_through(&centre).len(), MAX_PER_CELL);
+    }
+
+    #[test]
+    fn dead_position() {
+        let board: BitBoard = BitBoard::read(
+            "|RR.YRR.|
+             |YYRRYYR|
+             |RRYYRRY|
+             |YYRRYYR|
+             |YYRRYYR|
+             |YYRRYYR|",
+        );
+        assert!(is_dead(&board));
+        assert!(!can_still_win(&board, Token::Yellow));
+        assert_eq!(open_lines(&board, Token::Red).count(), 0);
+
+        let board: BitBoard = BitBoard::read(crate::test_positions::MEDIUM_0.board);
+        assert!(!is_dead(&board));
+        assert!(can_still_win(&board, Token::Yellow));
+        assert!(can_still_win(&board, Token::Red));
+    }
+
+    #[test]
+    fn token_mask_matches_get() {
+        let board: BitBoard = BitBoard::read(crate::test_positions::MEDIUM_1.board);
+        let yellow = token_mask(&board, Token::Yellow);
+        let red = token_mask(&board, Token::Red);
+        assert_eq!(yellow & red, 0);
+
+        for i in 0..CELL_COUNT {
+            let cell = index_cell(i);
+            let expected = match board.get(&cell) {
+                Some(Token::Yellow) => (true, false),
+                Some(Token::Red) => (false, true),
+                None => (false, false),
+            };
+            assert_eq!((yellow >> i & 1 == 1, red >> i & 1 == 1), expected);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+//! Connect four for more than two players, as a standalone module.
+//!
+//! `Token`, the `Board` traits and every solver built on them assume two players taking
+//! turns, down to `BitCol` storing one bit per token, so games of three or four players
+//! keep their own colours, board and searches here instead of generalising all of those.
+//! With two players the board and searches agree with the two-player solvers.
+
+use crate::algorithms::pruning::{DeadPosition, NoPruning, NodeCounter};
+use crate::basic::*;
+use crate::lines;
+use hashbrown::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// One of the players in a game of more than two, numbered in turn order from 0.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Colour(pub usize);
+
+impl Colour {
+    /// The letters for each colour in `MultiBoard::read` and `MultiBoard::to_string`.
+    pub const LETTERS: [char; 4] = ['Y', 'R', 'G', 'B'];
+
+    /// The colour of the first player.
+    pub const START: Colour = Colour(0);
+
+    /// Returns the colour that plays after this one, in a game of `players`.
+    pub fn next(&self, players: usize) -> Colour {
+        Colour((self.0 + 1) % players)
+    }
+
+    fn from_letter(letter: char) -> Option<Colour> {
+        Self::LETTERS.iter().position(|&l| l == letter).map(Colour)
+    }
+}
+
+/// The two-player colours, in the same turn order.
+impl From<Token> for Colour {
+    fn from(token: Token) -> Self {
+        match token {
+            Token::Yellow => Colour(0),
+            Token::Red => Colour(1),
+        }
+    }
+}
+
+impl Display for Colour {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Self::LETTERS[self.0])
+    }
+}
+
+/// A board `W` columns wide and `H` rows high for `N` players taking turns,
+/// won with `K` tokens of one colour in a line. The counterpart of `ArrayBoard`
+/// for up to four players.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MultiBoard<const W: usize, const H: usize, const K: usize, const N: usize> {
+    grid: [[Option<Colour>; H]; W],
+}
+
+impl<const W: usize, const H: usize, const K: usize, const N: usize> MultiBoard<W, H, K, N> {
+    const PLAYERS: () = assert!(
+        2 <= N && N <= Colour::LETTERS.len(),
+        "there must be two to four players"
+    );
+
+    /// An empty starting board.
+    pub const EMPTY: Self = {
+        let () = Self::PLAYERS;
+        MultiBoard {
+            grid: [[None; H]; W],
+        }
+    };
+
+    /// Returns the token at the given cell, or None if the cell is empty.
+    pub fn get(&self, cell: &Cell<W, H>) -> Option<Colour> {
+        self.grid[usize::from(cell.col)][usize::from(cell.row)]
+    }
+
+    /// Tries to place the given colour in the given column.
+    /// Returns `Some(Cell)` if successful, `None` if the column is full.
+    pub fn place(&mut self, col: &column::Idx<W>, colour: &Colour) -> Option<Cell<W, H>> {
+        let mut rows = row::Idx::<H>::BOTTOM_UP;
+        let row = rows.find(|&row| self.get(&Cell { col: *col, row }).is_none())?;
+        self.grid[usize::from(*col)][usize::from(row)] = Some(*colour);
+        Some(Cell { col: *col, row })
+    }
+
+    /// Removes the token at the given cell.
+    pub fn unplace(&mut self, cell: &Cell<W, H>) {
+        self.grid[usize::from(cell.col)][usize::from(cell.row)] = None;
+    }
+
+    /// Returns true if a token can be placed in the given column.
+    pub fn can_place(&self, col: &column::Idx<W>) -> bool {
+        self.get(&Cell {
+            col: *col,
+            row: row::Idx::TOP,
+        })
+        .is_none()
+    }
+
+    /// Returns true if every column is full.
+    pub fn is_full(&self) -> bool {
+        column::Idx::<W>::ALL.iter().all(|col| !self.can_place(col))
+    }
+
+    /// Checks if placing `colour` at the given empty cell would make a line of `K`.
+    pub fn would_win_at(&self, cell: &Cell<W, H>, colour: &Colour) -> bool {
+        // right, down, to bottom-right, to bottom-left
+        let dirs = [(1, 0), (0, -1), (1, -1), (-1, -1)];
+
+        dirs.iter().any(|&(dc, dr)| {
+            let mut count = 1;
+            for dir in [(dc, dr), (-dc, -dr)] {
+                let mut next = cell.try_shift(dir);
+                while let Some(curr) = next
+                    && self.get(&curr) == Some(*colour)
+                {
+                    count += 1;
+                    next = curr.try_shift(dir);
+                }
+            }
+            count >= K
+        })
+    }
+
+    /// Checks there is a line of `K` same-colour tokens that includes the given cell.
+    /// Panics if the cell is empty.
+    pub fn won_at(&self, cell: &Cell<W, H>) -> bool {
+        let Some(colour) = self.get(cell) else {
+            panic!("Tried to check for a win at an empty cell.")
+        };
+        self.would_win_at(cell, &colour)
+    }
+
+    /// Returns true if no colour can complete any line, so the game is a draw.
+    pub fn is_dead(&self) -> bool {
+        let mut masks = [0u128; N];
+        for col in column::Idx::<W>::ALL {
+            for row in row::Idx::<H>::BOTTOM_UP {
+                let cell = Cell { col, row };
+                if let Some(colour) = self.get(&cell) {
+                    masks[colour.0] |= lines::cell_mask(&cell);
+                }
+            }
+        }
+        let all = masks.iter().fold(0, |all, mask| all | mask);
+
+        // a line is open to a colour if it holds no tokens of any other
+        lines::Lines::<W, H, K>::MASKS
+            .iter()
+            .all(|line| masks.iter().all(|mask| line & (all & !mask) != 0))
+    }
+
+    /// Compute the current player based on the number of tokens on the board.
+    pub fn curr_player(&self) -> Colour {
+        let tokens = self
+            .grid
+            .iter()
+            .flatten()
+            .filter(|cell| cell.is_some())
+            .count();
+        Colour(tokens % N)
+    }
+
+    /// Plays the given columns from the empty board, with the players taking turns
+    /// from `Colour::START`. Returns None if a move is into a full column.
+    pub fn from_columns(cols: &[column::Idx<W>]) -> Option<Self> {
+        let mut board = Self::EMPTY;
+        let mut curr = Colour::START;
+        for col in cols {
+            board.place(col, &curr)?;
+            curr = curr.next(N);
+        }
+        Some(board)
+    }
+
+    /// Read a board from a string representation, with rows between `|`s from the top down,
+    /// as `Board::read` does, using the letters in `Colour::LETTERS`.
+    pub fn read(string: &str) -> Self {
+        let mut board = Self::EMPTY;
+        let mut counts = [0; N];
+
+        for line in string.split('|').rev() {
+            if line.trim().is_empty() || line.trim_start().starts_with('+') {
+                continue;
+            }
+            assert!(line.len() == W, "incorrect row width: `{line}`");
+
+            for (col, ch) in line.chars().enumerate() {
+                if ch == '.' || ch == ' ' {
+                    continue;
+                }
+                let colour = Colour::from_letter(ch)
+                    .filter(|colour| colour.0 < N)
+                    .unwrap_or_else(|| panic!("Invalid character in board string: {}", ch));
+                counts[colour.0] += 1;
+                let cell = board.place(&column::Idx::raw(col), &colour);
+                assert!(cell.is_some(), "too many rows in board string");
+            }
+        }
+
+        // in turn order, no player has fewer tokens than the next, nor one more than the last
+        assert!(
+            counts.windows(2).all(|pair| pair[0] >= pair[1]) && counts[0] - counts[N - 1] <= 1,
+            "incorrect numbers of tokens for each colour"
+        );
+        board
+    }
+}
+
+impl<const W: usize, const H: usize, const K: usize, const N: usize> Display
+    for MultiBoard<W, H, K, N>
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f)?;
+        for row in row::Idx::<H>::BOTTOM_UP.rev() {
+            write!(f, "|")?;
+            for col in column::Idx::<W>::ALL {
+                match self.get(&Cell { col, row }) {
+                    Some(colour) => write!(f, "{colour}")?,
+                    None => write!(f, " ")?,
+                }
+            }
+            writeln!(f, "|")?;
+        }
+        writeln!(f, "+{}+", "-".repeat(W))
+    }
+}
+
+/// The counterpart of `PruningRule` for games of more than two players, deciding
+/// the result of a position without searching it.
+pub trait MultiPruningRule {
+    /// Returns `Some(winner)` (with `None` for a draw) if the rule decides the position
+    /// with `curr` to play and `depth` plies left to search, or None if it has to be searched.
+    fn decide<const W: usize, const H: usize, const K: usize, const N: usize>(
+        &self,
+        board: &MultiBoard<W, H, K, N>,
+        curr: Colour,
+        depth: usize,
+    ) -> Option<Option<Colour>>;
+}
+
+impl<A: MultiPruningRule, C: MultiPruningRule> MultiPruningRule for (A, C) {
+    fn decide<const W: usize, const H: usize, const K: usize, const N: usize>(
+        &self,
+        board: &MultiBoard<W, H, K, N>,
+        curr: Colour,
+        depth: usize,
+    ) -> Option<Option<Colour>> {
+        self.0
+            .decide(board, curr, depth)
+            .or_else(|| self.1.decide(board, curr, depth))
+    }
+}
+
+impl MultiPruningRule for NoPruning {
+    fn decide<const W: usize, const H: usize, const K: usize, const N: usize>(
+        &self,
+        _board: &MultiBoard<W, H, K, N>,
+        _curr: Colour,
+        _depth: usize,
+    ) -> Option<Option<Colour>> {
+        None
+    }
+}
+
+impl MultiPruningRule for NodeCounter {
+    fn decide<const W: usize, const H: usize, const K: usize, const N: usize>(
+        &self,
+        _board: &MultiBoard<W, H, K, N>,
+        _curr: Colour,
+        _depth: usize,
+    ) -> Option<Option<Colour>> {
+        self.count();
+        None
+    }
+}
+
+impl MultiPruningRule for DeadPosition {
+    fn decide<const W: usize, const H: usize, const K: usize, const N: usize>(
+        &self,
+        board: &MultiBoard<W, H, K, N>,
+        _curr: Colour,
+        _depth: usize,
+    ) -> Option<Option<Colour>> {
+        board.is_dead().then_some(None)
+    }
+}
+
+/// Cache of search results, keyed by board, with the depth each was searched to.
+/// Every way to reach a board from the same root leaves the same depth to search,
+/// so only results for exactly that depth are looked up.
+type Cache<const W: usize, const H: usize, const K: usize, const N: usize, T> =
+    HashMap<MultiBoard<W, H, K, N>, (usize, T)>;
+
+/// Solves the position to the given depth with max-n search: every player picks the move
+/// best for themselves, preferring a win, then a game still undecided, and being
+/// indifferent between wins for the others. Ties go to the first move from the centre.
+/// Returns the winner under that play, or None if there is none within `depth` moves.
+pub fn max_n<const W: usize, const H: usize, const K: usize, const N: usize>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+) -> Option<Colour> {
+    max_n_with(board, depth, curr, &NoPruning)
+}
+
+/// `max_n`, cutting off any position decided by the given pruning rule.
+pub fn max_n_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    const N: usize,
+    R: MultiPruningRule,
+>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+    rule: &R,
+) -> Option<Colour> {
+    max_n_helper(board, depth, curr, &mut HashMap::new(), rule)
+}
+
+fn max_n_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    const N: usize,
+    R: MultiPruningRule,
+>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+    cache: &mut Cache<W, H, K, N, Option<Colour>>,
+    rule: &R,
+) -> Option<Colour> {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
+        return None;
+    }
+
+    if let Some(&(cached_depth, result)) = cache.get(board)
+        && cached_depth == depth
+    {
+        return result;
+    }
+
+    if let Some(result) = rule.decide(board, curr, depth) {
+        cache.insert(board.clone(), (depth, result));
+        return result;
+    }
+
+    let value = |result: Option<Colour>| match result {
+        Some(winner) if winner == curr => 2,
+        None => 1,
+        Some(_) => 0,
+    };
+    let mut best: Option<Option<Colour>> = None;
+
+    for col in column::Idx::<W>::CENTRED_FIRST {
+        let Some(cell) = board.place(&col, &curr) else {
+            continue;
+        };
+        let result = if board.won_at(&cell) {
+            Some(curr)
+        } else {
+            max_n_helper(board, depth - 1, curr.next(N), cache, rule)
+        };
+        board.unplace(&cell);
+
+        if best.is_none_or(|best| value(result) > value(best)) {
+            best = Some(result);
+        }
+        if result == Some(curr) {
+            break;
+        }
+    }
+
+    let result = best.flatten();
+    cache.insert(board.clone(), (depth, result));
+    result
+}
+
+/// Solves the position to the given depth with paranoid search: `player` assumes every
+/// other player works together against them. Returns 1 if `player` can force a win,
+/// -1 if the others can force a win for one of them, and 0 otherwise.
+pub fn paranoid<const W: usize, const H: usize, const K: usize, const N: usize>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+    player: Colour,
+) -> isize {
+    paranoid_with(board, depth, curr, player, &NoPruning)
+}
+
+/// `paranoid`, cutting off any position decided by the given pruning rule.
+pub fn paranoid_with<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    const N: usize,
+    R: MultiPruningRule,
+>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+    player: Colour,
+    rule: &R,
+) -> isize {
+    paranoid_helper(board, depth, curr, player, &mut HashMap::new(), rule)
+}
+
+fn paranoid_helper<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    const N: usize,
+    R: MultiPruningRule,
+>(
+    board: &mut MultiBoard<W, H, K, N>,
+    depth: usize,
+    curr: Colour,
+    player: Colour,
+    cache: &mut Cache<W, H, K, N, isize>,
+    rule: &R,
+) -> isize {
+    // a full board is a draw, however much depth is left
+    if depth == 0 || board.is_full() {
+        return 0;
+    }
+
+    if let Some(&(cached_depth, score)) = cache.get(board)
+        && cached_depth == depth
+    {
+        return score;
+    }
+
+    if let Some(result) = rule.decide(board, curr, depth) {
+        let score = match result {
+            Some(winner) if winner == player => 1,
+            Some(_) => -1,
+            None => 0,
+        };
+        cache.insert(board.clone(), (depth, score));
+        return score;
+    }
+
+    let maximising = curr == player;
+    // the best score for the player to move, and the worst
+    let (target, mut best) = if maximising { (1, -1) } else { (-1, 1) };
+
+    for col in column::Idx::<W>::CENTRED_FIRST {
+        let Some(cell) = board.place(&col, &curr) else {
+            continue;
+        };
+        let score = match board.won_at(&cell) {
+            true if maximising => 1,
+            true => -1,
+            false => paranoid_helper(board, depth - 1, curr.next(N), player, cache, rule),
+        };
+        board.unplace(&cell);
+
+        best = if maximising {
+            best.max(score)
+        } else {
+            best.min(score)
+        };
+        if best == target {
+            break;
+        }
+    }
+
+    cache.insert(board.clone(), (depth, best));
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::board::{Board, bit_board::BitBoard};
+    use crate::random::Rng;
+
+    fn cols<const W: usize>(cols: &[usize]) -> Vec<column::Idx<W>> {
+        cols.iter().map(|&col| column::Idx::raw(col)).collect()
+    }
+
+    #[test]
+    fn turns_and_display() {
+        let board = MultiBoard::<5, 4, 3, 3>::from_columns(&cols(&[2, 2, 1, 3, 2, 0, 4])).unwrap();
+        let cell = |col, row| Cell {
+            col: column::Idx::raw(col),
+            row: row::Idx::raw(row),
+        };
+        assert_eq!(board.curr_player(), Colour(1));
+        assert_eq!(board.get(&cell(2, 1)), Some(Colour(1)));
+        assert_eq!(board.get(&cell(3, 0)), Some(Colour(0)));
+        assert_eq!(Colour(2).to_string(), "G");
+        assert_eq!(Colour(3).next(4), Colour::START);
+        assert_eq!(MultiBoard::<5, 4, 3, 3>::read(&board.to_string()), board);
+        assert_eq!(
+            board,
+            MultiBoard::read(
+                "|.....|
+                 |..R..|
+                 |..R..|
+                 |GGYYY|",
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "incorrect numbers of tokens")]
+    fn rejects_skipped_turns() {
+        MultiBoard::<5, 4, 3, 3>::read("|YYG..|");
+    }
+
+    #[test]
+    fn two_players_agree_with_minimax() {
+        let mut rng = Rng::seeded(0);
+        for _ in 0..50 {
+            let cols = cols::<4>(&(0..rng.below(8)).map(|_| rng.below(4)).collect::<Vec<_>>());
+            let Some(mut multi) = MultiBoard::<4, 4, 3, 2>::from_columns(&cols) else {
+                continue;
+            };
+            let mut board = BitBoard::<4, 4, 3>::EMPTY;
+            let mut curr = Token::START;
+            for col in &cols {
+                board.place(col, &curr);
+                curr = curr.next();
+            }
+
+            let expected = minimax_cached(board, 6, curr).map(Colour::from);
+            let curr = multi.curr_player();
+            assert_eq!(max_n(&mut multi, 6, curr), expected);
+            assert_eq!(max_n_with(&mut multi, 6, curr, &DeadPosition), expected);
+            let score = match expected {
+                Some(winner) if winner == curr => 1,
+                Some(_) => -1,
+                None => 0,
+            };
+            assert_eq!(paranoid(&mut multi, 6, curr, curr), score);
+            assert_eq!(
+                paranoid_with(&mut multi, 6, curr, curr, &DeadPosition),
+                score
+            );
+        }
+    }
+
+    #[test]
+    fn three_players() {
+        // Red threatens to complete row 0, and when each plays for themselves
+        // Green or Yellow blocks it, but Green and Red working together beat Yellow
+        let mut board = MultiBoard::<5, 4, 3, 3>::read(
+            "|.....|
+             |G....|
+             |YRR.Y|",
+        );
+        let curr = board.curr_player();
+        assert_eq!(curr, Colour(2));
+        assert_eq!(max_n(&mut board, 3, curr), None);
+        assert_eq!(max_n(&mut board, 1, Colour(1)), Some(Colour(1)));
+        assert_eq!(paranoid(&mut board, 3, curr, Colour(0)), 0);
+        assert_eq!(paranoid(&mut board, 4, curr, Colour(0)), -1);
+        assert_eq!(paranoid(&mut board, 4, curr, curr), 0);
+
+        // on an empty 4x3 board, any two players can gang up on the third,
+        // but no one wins when each plays for themselves
+        let mut board = MultiBoard::<4, 3, 3, 3>::EMPTY;
+        for player in 0..3 {
+            assert_eq!(paranoid(&mut board, 12, Colour::START, Colour(player)), -1);
+        }
+        assert_eq!(max_n(&mut board, 12, Colour::START), None);
+        assert_eq!(
+            max_n_with(&mut board, 12, Colour::START, &DeadPosition),
+            None
+        );
+    }
+
+    #[test]
+    fn dead_positions() {
+        // every row of three holds two colours, and no column has room for three
+        let board = MultiBoard::<3, 2, 3, 3>::read(
+            "|GYR|
+             |YRG|",
+        );
+        assert!(board.is_dead());
+        assert!(!MultiBoard::<3, 2, 3, 3>::read("|YRG|").is_dead());
+        assert!(!MultiBoard::<4, 3, 3, 3>::EMPTY.is_dead());
+    }
+
+    #[test]
+    fn dead_positions_cut_the_search() {
+        let mut board = MultiBoard::<4, 3, 3, 3>::EMPTY;
+        let all = NodeCounter::default();
+        let pruned = (NodeCounter::default(), DeadPosition);
+        assert_eq!(max_n_with(&mut board, 12, Colour::START, &all), None);
+        assert_eq!(max_n_with(&mut board, 12, Colour::START, &pruned), None);
+        assert!(pruned.0.nodes() < all.nodes());
+    }
+}
@@ -0,0 +1,693 @@
+use crate::basic::*;
+use crate::lines;
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// The most cells in a pattern.
+pub const MAX_CELLS: usize = 16;
+
+/// The most patterns in a pattern set, counting each orientation added.
+pub const MAX_PATTERNS: usize = 32;
+
+/// The most places the patterns of a set can fit on any board whose cells fit in a `u128`
+/// bitmask: at most one per pattern from each cell.
+pub const MAX_COUNT: usize = MAX_PATTERNS * u128::BITS as usize;
+
+/// A shape of cells that wins when every cell holds the same player's token,
+/// wherever it sits on the board. Stored as (col, row) offsets from the bottom-left
+/// of the shape, sorted, so that equal shapes compare equal.
+/// Patterns are built in const fns, so a `WinPatterns` set is placed when compiled.
+///
+/// Written as rows from the top down, separated by `/`, with `#` for a cell of the
+/// shape and `.` for a gap: `##/##` is a 2x2 square and `#./#./##` an L.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    /// The offsets, of which only the first `len` are used. The rest are kept at (0, 0).
+    offsets: [(isize, isize); MAX_CELLS],
+    len: usize,
+}
+
+/// Returns true if `a` sorts before `b`, by column then row.
+const fn before(a: (isize, isize), b: (isize, isize)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+impl Pattern {
+    /// Makes a pattern of the given offsets, wherever they are.
+    /// Panics if there are none, or more than `MAX_CELLS`.
+    pub const fn new(offsets: &[(isize, isize)]) -> Self {
+        assert!(!offsets.is_empty(), "a pattern needs at least one cell");
+        assert!(
+            offsets.len() <= MAX_CELLS,
+            "a pattern has at most `MAX_CELLS` cells"
+        );
+
+        let (mut min_col, mut min_row) = offsets[0];
+        let mut i = 1;
+        while i < offsets.len() {
+            if offsets[i].0 < min_col {
+                min_col = offsets[i].0;
+            }
+            if offsets[i].1 < min_row {
+                min_row = offsets[i].1;
+            }
+            i += 1;
+        }
+
+        // insertion sort, skipping repeated offsets
+        let mut pattern = Pattern {
+            offsets: [(0, 0); MAX_CELLS],
+            len: 0,
+        };
+        let mut i = 0;
+        while i < offsets.len() {
+            let offset = (offsets[i].0 - min_col, offsets[i].1 - min_row);
+            let mut at = pattern.len;
+            while at > 0 && before(offset, pattern.offsets[at - 1]) {
+                at -= 1;
+            }
+            let repeated = at > 0
+                && pattern.offsets[at - 1].0 == offset.0
+                && pattern.offsets[at - 1].1 == offset.1;
+            if !repeated {
+                let mut j = pattern.len;
+                while j > at {
+                    pattern.offsets[j] = pattern.offsets[j - 1];
+                    j -= 1;
+                }
+                pattern.offsets[at] = offset;
+                pattern.len += 1;
+            }
+            i += 1;
+        }
+        pattern
+    }
+
+    /// A straight line of `length` cells in the given direction.
+    pub const fn line(length: usize, dir: (isize, isize)) -> Self {
+        assert!(
+            length <= MAX_CELLS,
+            "a pattern has at most `MAX_CELLS` cells"
+        );
+        let mut offsets = [(0, 0); MAX_CELLS];
+        let mut i = 0;
+        while i < length {
+            offsets[i] = (i as isize * dir.0, i as isize * dir.1);
+            i += 1;
+        }
+        Pattern::new(offsets.split_at(length).0)
+    }
+
+    /// The offsets of the cells of the pattern, sorted.
+    pub const fn cells(&self) -> &[(isize, isize)] {
+        self.offsets.split_at(self.len).0
+    }
+
+    /// Returns the pattern reflected left to right.
+    pub const fn mirrored(&self) -> Self {
+        let mut offsets = self.offsets;
+        let mut i = 0;
+        while i < self.len {
+            offsets[i] = (-offsets[i].0, offsets[i].1);
+            i += 1;
+        }
+        Pattern::new(offsets.split_at(self.len).0)
+    }
+
+    /// Returns the pattern turned a quarter anticlockwise.
+    pub const fn rotated(&self) -> Self {
+        let mut offsets = self.offsets;
+        let mut i = 0;
+        while i < self.len {
+            offsets[i] = (-offsets[i].1, offsets[i].0);
+            i += 1;
+        }
+        Pattern::new(offsets.split_at(self.len).0)
+    }
+
+    /// Returns true if the patterns are the same shape.
+    /// The same as `==`, which const fns cannot call.
+    const fn same(&self, other: &Pattern) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        let mut i = 0;
+        while i < self.len {
+            if self.offsets[i].0 != other.offsets[i].0 || self.offsets[i].1 != other.offsets[i].1 {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns every distinct way the pattern can be turned or reflected,
+    /// starting with the pattern itself.
+    pub fn orientations(&self) -> Vec<Pattern> {
+        PatternSet::new(&[*self])
+            .with_orientations()
+            .patterns()
+            .to_vec()
+    }
+
+    /// Returns the bitmask of the pattern with its bottom-left at (`col`, `row`) on a `w` by
+    /// `h` board, or None if it does not fit. On a cylinder it wraps round from the last
+    /// column to the first, but still does not fit if it would cover a cell twice.
+    const fn mask_at(
+        &self,
+        w: usize,
+        h: usize,
+        col: usize,
+        row: usize,
+        cylinder: bool,
+    ) -> Option<u128> {
+        let mut mask: u128 = 0;
+        let mut i = 0;
+        while i < self.len {
+            let c = col as isize + self.offsets[i].0;
+            let r = row as isize + self.offsets[i].1;
+            if r < 0 || r >= h as isize || (!cylinder && (c < 0 || c >= w as isize)) {
+                return None;
+            }
+            let c = c.rem_euclid(w as isize) as usize;
+            mask |= 1 << (c * h + r as usize);
+            i += 1;
+        }
+        if mask.count_ones() as usize == self.len {
+            Some(mask)
+        } else {
+            None
+        }
+    }
+
+    /// Parses a pattern, as `from_str` does, in a const fn.
+    const fn parse(bytes: &[u8]) -> Result<Self, &'static str> {
+        let mut offsets = [(0, 0); MAX_CELLS];
+        let mut len = 0;
+        let (mut col, mut row) = (0, 0);
+
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'#' => {
+                    if len == MAX_CELLS {
+                        return Err("too many cells in a pattern");
+                    }
+                    // rows are written from the top down, and `new` moves them up from 0
+                    offsets[len] = (col, -row);
+                    len += 1;
+                    col += 1;
+                }
+                b'.' => col += 1,
+                b'/' => {
+                    col = 0;
+                    row += 1;
+                }
+                _ => return Err("invalid character in a pattern"),
+            }
+            i += 1;
+        }
+
+        if len == 0 {
+            return Err("a pattern has no cells");
+        }
+        Ok(Pattern::new(offsets.split_at(len).0))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(string.trim().as_bytes()).map_err(|err| format!("{err}: `{string}`"))
+    }
+}
+
+impl Debug for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Pattern").field(&self.cells()).finish()
+    }
+}
+
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let cells = self.cells();
+        let width = cells.iter().map(|&(col, _)| col).max().unwrap_or(0) + 1;
+        let height = cells.iter().map(|&(_, row)| row).max().unwrap_or(0) + 1;
+
+        let rows: Vec<String> = (0..height)
+            .rev()
+            .map(|row| {
+                (0..width)
+                    .map(|col| {
+                        if cells.contains(&(col, row)) {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        write!(f, "{}", rows.join("/"))
+    }
+}
+
+/// A set of patterns, any of which wins: the pluggable counterpart of the straight lines
+/// of `Lines`. A board won by a set of patterns is a `Patterned` board.
+///
+/// Written as patterns separated by whitespace, so `##/## #/#/#/#` wins with a 2x2 square
+/// or a vertical line of four.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct PatternSet {
+    /// The patterns, of which only the first `len` are used.
+    patterns: [Pattern; MAX_PATTERNS],
+    len: usize,
+}
+
+impl PatternSet {
+    /// Makes a set of the given patterns. Panics if there are more than `MAX_PATTERNS`.
+    pub const fn new(patterns: &[Pattern]) -> Self {
+        let mut set = PatternSet {
+            patterns: [Pattern::line(1, (0, 0)); MAX_PATTERNS],
+            len: 0,
+        };
+        let mut i = 0;
+        while i < patterns.len() {
+            set = set.with(patterns[i]);
+            i += 1;
+        }
+        set
+    }
+
+    /// Returns the set with the pattern added to the end.
+    /// Panics if the set already has `MAX_PATTERNS`.
+    const fn with(mut self, pattern: Pattern) -> Self {
+        assert!(
+            self.len < MAX_PATTERNS,
+            "a pattern set has at most `MAX_PATTERNS` patterns"
+        );
+        self.patterns[self.len] = pattern;
+        self.len += 1;
+        self
+    }
+
+    /// Straight lines of `length` in every direction, the usual way to win.
+    pub const fn lines(length: usize) -> Self {
+        PatternSet::new(&[
+            // right, down, to bottom-right, to bottom-left
+            Pattern::line(length, (1, 0)),
+            Pattern::line(length, (0, -1)),
+            Pattern::line(length, (1, -1)),
+            Pattern::line(length, (-1, -1)),
+        ])
+    }
+
+    /// The patterns in the set.
+    pub const fn patterns(&self) -> &[Pattern] {
+        self.patterns.split_at(self.len).0
+    }
+
+    /// Returns true if the set has a pattern the same shape as the given one.
+    const fn contains(&self, pattern: &Pattern) -> bool {
+        let mut i = 0;
+        while i < self.len {
+            if self.patterns[i].same(pattern) {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    /// Returns the set with every orientation of each of its patterns added.
+    pub const fn with_orientations(&self) -> Self {
+        let mut set = PatternSet::new(&[]);
+        let mut i = 0;
+        while i < self.len {
+            let mut pattern = self.patterns[i];
+            // four turns of the pattern, then four of its mirror image
+            let mut turn = 0;
+            while turn < 8 {
+                if turn == 4 {
+                    pattern = self.patterns[i].mirrored();
+                }
+                if !set.contains(&pattern) {
+                    set = set.with(pattern);
+                }
+                pattern = pattern.rotated();
+                turn += 1;
+            }
+            i += 1;
+        }
+        set
+    }
+
+    /// Returns true if the mirror image of every pattern is in the set.
+    pub const fn symmetric(&self) -> bool {
+        let mut i = 0;
+        while i < self.len {
+            if !self.contains(&self.patterns[i].mirrored()) {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Makes the masks of every distinct place a pattern fits on a `w` by `h` board,
+    /// wrapping round from the last column into the first if `cylinder`.
+    const fn make_masks(&self, w: usize, h: usize, cylinder: bool) -> ([u128; MAX_COUNT], usize) {
+        assert!(
+            w * h <= u128::BITS as usize,
+            "the board does not fit a u128"
+        );
+        let mut masks = [0; MAX_COUNT];
+        let mut n = 0;
+
+        let mut p = 0;
+        while p < self.len {
+            let mut col = 0;
+            while col < w {
+                let mut row = 0;
+                while row < h {
+                    if let Some(mask) = self.patterns[p].mask_at(w, h, col, row, cylinder) {
+                        // a pattern all the way round a cylinder fits from every column,
+                        // and one pattern can be another turned round a cylinder
+                        let mut seen = false;
+                        let mut j = 0;
+                        while j < n {
+                            seen |= masks[j] == mask;
+                            j += 1;
+                        }
+                        if !seen {
+                            masks[n] = mask;
+                            n += 1;
+                        }
+                    }
+                    row += 1;
+                }
+                col += 1;
+            }
+            p += 1;
+        }
+        (masks, n)
+    }
+
+    /// Returns the bitmask of every distinct place a pattern fits on a `W` by `H` board,
+    /// in the form of `Lines::masks`, wrapping round from the last column into the first
+    /// if `cylinder`.
+    pub fn masks<const W: usize, const H: usize>(&self, cylinder: bool) -> Vec<u128> {
+        let (masks, count) = self.make_masks(W, H, cylinder);
+        masks[..count].to_vec()
+    }
+
+    /// Parses a pattern set, as `from_str` does, in a const fn.
+    const fn parse(string: &str) -> Result<Self, &'static str> {
+        let mut set = PatternSet::new(&[]);
+        let mut rest = string.as_bytes();
+        while !rest.is_empty() {
+            // split off the next word
+            let mut end = 0;
+            while end < rest.len() && !rest[end].is_ascii_whitespace() {
+                end += 1;
+            }
+            let (word, after) = rest.split_at(end);
+            if !word.is_empty() {
+                if set.len == MAX_PATTERNS {
+                    return Err("too many patterns in a pattern set");
+                }
+                match Pattern::parse(word) {
+                    Ok(pattern) => set = set.with(pattern),
+                    Err(err) => return Err(err),
+                }
+            }
+            rest = if after.is_empty() {
+                after
+            } else {
+                after.split_at(1).1
+            };
+        }
+
+        if set.len == 0 {
+            return Err("a pattern set needs at least one pattern");
+        }
+        Ok(set)
+    }
+}
+
+impl Debug for PatternSet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("PatternSet").field(&self.patterns()).finish()
+    }
+}
+
+impl FromStr for PatternSet {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        PatternSet::parse(string).map_err(|err| format!("{err}: `{string}`"))
+    }
+}
+
+/// A pattern set fixed for a type of board, so a `Patterned` board can have an `EMPTY`
+/// const and hand out its masks from `Placements`, as `Lines` does.
+pub trait WinPatterns {
+    /// The patterns, written as for `PatternSet`.
+    const PATTERNS: &'static str;
+
+    /// True if every turn and reflection of the patterns wins too.
+    const ORIENTATIONS: bool = false;
+
+    /// The pattern set, parsed when compiled, so invalid patterns fail the build.
+    const SET: PatternSet = {
+        let set = match PatternSet::parse(Self::PATTERNS) {
+            Ok(set) => set,
+            Err(err) => panic!("{}", err),
+        };
+        if Self::ORIENTATIONS {
+            set.with_orientations()
+        } else {
+            set
+        }
+    };
+
+    /// True if the mirror image of every pattern wins too.
+    const SYMMETRIC: bool = Self::SET.symmetric();
+}
+
+/// The places the patterns of `P` fit on a `W` by `H` board, in the form of `Lines`.
+pub struct Placements<P, const W: usize, const H: usize>(PhantomData<P>);
+
+impl<P: WinPatterns, const W: usize, const H: usize> Placements<P, W, H> {
+    const TABLE: ([u128; MAX_COUNT], usize) = P::SET.make_masks(W, H, false);
+    const CYLINDER_TABLE: ([u128; MAX_COUNT], usize) = P::SET.make_masks(W, H, true);
+
+    /// The bitmask of each place a pattern fits.
+    pub const MASKS: &'static [u128] = Self::TABLE.0.split_at(Self::TABLE.1).0;
+
+    /// The bitmask of each place a pattern fits on a cylinder,
+    /// including those wrapping round from the last column into the first.
+    pub const CYLINDER_MASKS: &'static [u128] =
+        Self::CYLINDER_TABLE.0.split_at(Self::CYLINDER_TABLE.1).0;
+
+    /// Returns `CYLINDER_MASKS` on a cylinder, else `MASKS`.
+    pub const fn masks(cylinder: bool) -> &'static [u128] {
+        if cylinder {
+            Self::CYLINDER_MASKS
+        } else {
+            Self::MASKS
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::evaluators::threats;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::algorithms::minimax_symm::minimax_symm;
+    use crate::board::bit_board::BitBoard;
+    use crate::board::patterned::Patterned;
+    use crate::board::{Board, CloneBoard};
+    use crate::random::Rng;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Fours;
+
+    impl WinPatterns for Fours {
+        const PATTERNS: &'static str = "#### #.../.#../..#./...#";
+        const ORIENTATIONS: bool = true;
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Squares;
+
+    impl WinPatterns for Squares {
+        const PATTERNS: &'static str = "##/##";
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Ell;
+
+    impl WinPatterns for Ell {
+        const PATTERNS: &'static str = "#./##";
+    }
+
+    #[test]
+    fn patterns_round_trip() {
+        for string in ["##/##", "#./#./##", ".#/##", "#/#/#/#", "#..#"] {
+            let pattern: Pattern = string.parse().unwrap();
+            assert_eq!(pattern.to_string(), string);
+        }
+        assert_eq!("..##/..##".parse::<Pattern>(), "##/##".parse::<Pattern>());
+        assert!("#x/##".parse::<Pattern>().is_err());
+        assert!("../..".parse::<Pattern>().is_err());
+        assert!("".parse::<PatternSet>().is_err());
+        assert_eq!(Pattern::line(3, (1, -1)).to_string(), "#../.#./..#");
+    }
+
+    #[test]
+    fn counts_orientations() {
+        let count = |string: &str| string.parse::<Pattern>().unwrap().orientations().len();
+        assert_eq!(count("##/##"), 1);
+        assert_eq!(count("####"), 2);
+        assert_eq!(count("#./##"), 4);
+        assert_eq!(count("#./#./##"), 8);
+
+        let set: PatternSet = "#./#./##".parse().unwrap();
+        assert!(!set.symmetric());
+        assert!(set.with_orientations().symmetric());
+        assert!(PatternSet::lines(4).symmetric());
+        assert_eq!(Fours::SET.with_orientations(), Fours::SET);
+    }
+
+    #[test]
+    fn lines_place_as_lines() {
+        fn sorted(mut masks: Vec<u128>) -> Vec<u128> {
+            masks.sort();
+            masks
+        }
+        for cylinder in [false, true] {
+            assert_eq!(
+                sorted(PatternSet::lines(4).masks::<7, 6>(cylinder)),
+                sorted(lines::Lines::<7, 6, 4>::masks(cylinder).to_vec())
+            );
+            assert_eq!(
+                sorted(PatternSet::lines(3).masks::<5, 4>(cylinder)),
+                sorted(lines::Lines::<5, 4, 3>::masks(cylinder).to_vec())
+            );
+        }
+        // a row of four only fits a cylinder four wide one way round
+        assert_eq!(PatternSet::lines(4).masks::<4, 1>(true).len(), 1);
+        assert_eq!(PatternSet::lines(5).masks::<4, 1>(true), []);
+    }
+
+    /// Plays random games, checking the patterns agree with the lines of `B` on every cell.
+    fn agrees_with_lines<const W: usize, const H: usize, const K: usize, B>(games: usize)
+    where
+        B: Board<W, H, K> + Clone,
+        Patterned<B, Fours>: Board<W, H, K>,
+    {
+        let mut rng = Rng::seeded(0);
+        for _ in 0..games {
+            let mut board = B::EMPTY;
+            let mut patterned = Patterned::<B, Fours>::EMPTY;
+            let mut curr = Token::START;
+            while !board.is_full() {
+                let col = column::Idx::raw(rng.below(W));
+                let Some(cell) = board.place(&col, &curr) else {
+                    continue;
+                };
+                patterned.place(&col, &curr);
+                assert_eq!(patterned.won_at(&cell), board.won_at(&cell));
+                assert_eq!(
+                    patterned.count_adjacent_at(&cell).is_none(),
+                    board.count_adjacent_at(&cell).is_none()
+                );
+                if board.won_at(&cell) {
+                    break;
+                }
+                for token in [Token::Yellow, Token::Red] {
+                    let mut expected: Vec<Cell<W, H>> = column::Idx::<W>::ALL
+                        .iter()
+                        .flat_map(|&col| row::Idx::<H>::BOTTOM_UP.map(move |row| Cell { col, row }))
+                        .filter(|cell| board.piece(cell).is_none())
+                        .filter(|cell| board.would_win_at(cell, &token))
+                        .collect();
+                    expected.sort_by_key(lines::cell_index);
+                    for cell in &expected {
+                        assert!(patterned.would_win_at(cell, &token));
+                    }
+                    assert_eq!(threats(&patterned, token), expected);
+                }
+                curr = curr.next();
+            }
+        }
+    }
+
+    #[test]
+    fn lines_agree_with_boards() {
+        agrees_with_lines::<7, 6, 4, BitBoard>(20);
+        agrees_with_lines::<7, 6, 4, BitBoard<7, 6, 4, true>>(20);
+        agrees_with_lines::<5, 5, 4, BitBoard<5, 5, 4, true>>(20);
+
+        let board = Patterned::<_, Fours>::new(BitBoard::<4, 4, 4>::EMPTY);
+        assert_eq!(
+            minimax_cached(board, 16, Token::START),
+            minimax_cached(BitBoard::<4, 4, 4>::EMPTY, 16, Token::START)
+        );
+    }
+
+    #[test]
+    fn squares_win() {
+        let board: Patterned<BitBoard<5, 4, 4>, Squares> = Patterned::read(
+            "|.....|
+             |.....|
+             |Y....|
+             |YYRR.|",
+        );
+        let cell = |col, row| Cell {
+            col: column::Idx::raw(col),
+            row: row::Idx::raw(row),
+        };
+        assert_eq!(threats(&board, Token::Yellow), [cell(1, 1)]);
+        assert_eq!(threats(&board, Token::Red), []);
+        assert_eq!(board.count_adjacent_at(&cell(0, 1)), Some((1, 0)));
+
+        // Red has to block the square, and Yellow would take it
+        assert_eq!(minimax_cached(board.clone(), 2, Token::Red), None);
+        assert_eq!(
+            minimax_cached(board.clone(), 1, Token::Yellow),
+            Some(Token::Yellow)
+        );
+        assert_eq!(
+            minimax_cached(board.board().clone(), 1, Token::Yellow),
+            None
+        );
+    }
+
+    #[test]
+    fn solves_asymmetric_patterns() {
+        // a single L is not its own mirror image, so Yellow's win here is not one in the mirror
+        let board: Patterned<BitBoard<4, 4, 4>, Ell> = Patterned::read(
+            "|Y...|
+             |Y.RR|",
+        );
+        assert!(!Patterned::<BitBoard<4, 4, 4>, Ell>::mirror_symmetric());
+        assert_ne!(board.canonical(), board.flipped().canonical());
+
+        assert_eq!(
+            minimax_symm(board.clone(), 1, Token::Yellow),
+            Some(Token::Yellow)
+        );
+        assert_eq!(minimax_symm(board.flipped(), 1, Token::Yellow), None);
+
+        let empty = Patterned::<BitBoard<4, 4, 4>, Ell>::EMPTY;
+        assert_eq!(
+            minimax_symm(empty.clone(), 16, Token::START),
+            minimax_cached(empty, 16, Token::START)
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use crate::algorithms::minimax_cached::minimax_cached_with;
+use crate::algorithms::pruning::DeadPosition;
+use crate::algorithms::win_in_n::win_in_n;
+use crate::basic::*;
+use crate::board::bit_board::BitBoard;
+use crate::board::{Board, CloneBoard};
+use crate::random::Rng;
+use std::fmt::{Display, Formatter};
+
+/// How hard a puzzle is, graded by the number of positions searched to solve it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Grades a puzzle by the number of nodes searched to solve it.
+    pub fn of(nodes: usize) -> Difficulty {
+        match nodes {
+            ..1_000 => Difficulty::Easy,
+            1_000..50_000 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    /// The number of positions of this difficulty already in `test_positions`,
+    /// which generated puzzles are numbered after so their names do not clash.
+    pub fn taken(self) -> usize {
+        match self {
+            Difficulty::Easy => 3,
+            Difficulty::Medium => 4,
+            Difficulty::Hard => 3,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "EASY"),
+            Difficulty::Medium => write!(f, "MEDIUM"),
+            Difficulty::Hard => write!(f, "HARD"),
+        }
+    }
+}
+
+/// The most empty cells a puzzle can have, few enough to solve every other move
+/// to the end of the game and check that none of them wins as well.
+pub const MAX_EMPTY_CELLS: usize = 20;
+
+/// A position with a unique winning move and a forced win in exactly `depth` plies.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Puzzle {
+    pub board: BitBoard,
+    pub winner: Token,
+    pub depth: usize,
+    /// The key move followed by the longest defence and its refutations.
+    pub solution: Vec<column::Idx>,
+    /// The number of positions searched to solve the puzzle.
+    pub nodes: usize,
+    /// Numbers the puzzles of each difficulty in the order they were found, after those
+    /// already in `test_positions`, naming them `EASY_3`, `EASY_4` and so on.
+    pub number: usize,
+}
+
+impl Puzzle {
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::of(self.nodes)
+    }
+}
+
+/// Prints the puzzle as a `test_positions::Position`, with the solution in a comment.
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let solution: Vec<String> = self.solution.iter().map(|col| col.to_string()).collect();
+        writeln!(
+            f,
+            "// {} ({} nodes), solution: {}",
+            self.difficulty(),
+            self.nodes,
+            solution.join(" ")
+        )?;
+        writeln!(
+            f,
+            "pub const {}_{}: Position = Position {{",
+            self.difficulty(),
+            self.number
+        )?;
+        writeln!(f, "    board: \"{}\",", diagram(&self.board, 12))?;
+        writeln!(f, "    winner: Some(Token::{:?}),", self.winner)?;
+        writeln!(f, "    depth: {},", self.depth)?;
+        write!(f, "}};")
+    }
+}
+
+/// Returns the board in the format read by `Board::read`, top row first,
+/// with every row after the first indented by `indent` spaces.
+pub fn diagram<B: Board>(board: &B, indent: usize) -> String {
+    let rows: Vec<String> = row::BOTTOM_UP
+        .rev()
+        .map(|row| {
+            let cells: String = column::IDXS
+                .into_iter()
+                .map(|col| match board.piece(&Cell { col, row }) {
+                    Some(Piece::Token(Token::Yellow)) => 'Y',
+                    Some(Piece::Token(Token::Red)) => 'R',
+                    Some(Piece::Blocker) => BLOCKER,
+                    None => '.',
+                })
+                .collect();
+            format!("|{cells}|")
+        })
+        .collect();
+    rows.join(&format!("\n{:indent$}", ""))
+}
+
+/// Returns true if no move but `key` wins for `curr` at all, solving each of the others
+/// through the `empty` cells left, so that a slower win is not a second solution.
+fn only_winning_move(board: &BitBoard, key: &column::Idx, empty: usize, curr: Token) -> bool {
+    column::IDXS
+        .into_iter()
+        .filter(|col| col != key)
+        .filter_map(|col| board.clone_and_place(&col, &curr))
+        .all(|(next, cell)| {
+            !next.won_at(&cell)
+                && minimax_cached_with(next, empty - 1, curr.next(), &DeadPosition) != Some(curr)
+        })
+}
+
+/// Generates puzzles by searching the positions of random games.
+pub struct Generator {
+    rng: Rng,
+    /// The length of the forced win, in plies, that the puzzles must have.
+    pub depth: usize,
+    /// If true, the random players never make a move that loses immediately
+    /// when they have another, and always block an immediate threat.
+    pub semi_random: bool,
+    /// The number of puzzles found so far of each difficulty.
+    found: [usize; 3],
+}
+
+impl Generator {
+    pub fn new(seed: u64, depth: usize, semi_random: bool) -> Self {
+        Generator {
+            rng: Rng::seeded(seed),
+            depth,
+            semi_random,
+            found: [0; 3],
+        }
+    }
+
+    /// Plays up to `games` random games, returning the first puzzle found.
+    pub fn next_puzzle(&mut self, games: usize) -> Option<Puzzle> {
+        let mut puzzle = (0..games).find_map(|_| self.search_game())?;
+        let found = &mut self.found[puzzle.difficulty() as usize];
+        puzzle.number = puzzle.difficulty().taken() + *found;
+        *found += 1;
+        Some(puzzle)
+    }
+
+    /// Plays a single random game, returning the first position in it that makes a puzzle.
+    fn search_game(&mut self) -> Option<Puzzle> {
+        let mut board = BitBoard::EMPTY;
+        let mut curr = Token::START;
+        let mut empty = column::COUNT * row::COUNT;
+
+        while !board.is_full() {
+            if empty <= MAX_EMPTY_CELLS
+                && let Some(win) = win_in_n(&mut board, self.depth, curr)
+                && win.plies == self.depth
+                && win.is_unique()
+                && only_winning_move(&board, &win.tree.key, empty, curr)
+            {
+                return Some(Puzzle {
+                    board,
+                    winner: curr,
+                    depth: win.plies,
+                    solution: win.tree.main_line(),
+                    nodes: win.nodes,
+                    number: 0,
+                });
+            }
+
+            let col = self.choose_move(&board, curr)?;
+            let cell = board.place(&col, &curr).expect("the move is playable");
+            if board.won_at(&cell) {
+                return None;
+            }
+            curr = curr.next();
+            empty -= 1;
+        }
+        None
+    }
+
+    fn choose_move(&mut self, board: &BitBoard, curr: Token) -> Option<column::Idx> {
+        let moves: Vec<(column::Idx, Cell)> = column::IDXS
+            .into_iter()
+            .filter_map(|col| Some((col, board.next_cell(&col)?)))
+            .collect();
+        if !self.semi_random {
+            return self.rng.choose(&moves).map(|&(col, _)| col);
+        }
+
+        let blocks: Vec<_> = moves
+            .iter()
+            .filter(|(_, cell)| board.would_win_at(cell, &curr.next()))
+            .copied()
+            .collect();
+        let safe: Vec<_> = moves
+            .iter()
+            .filter(|(_, cell)| {
+                cell.try_shift((0, 1))
+                    .is_none_or(|above| !board.would_win_at(&above, &curr.next()))
+            })
+            .copied()
+            .collect();
+
+        let candidates = if !blocks.is_empty() {
+            blocks
+        } else if !safe.is_empty() {
+            safe
+        } else {
+            moves
+        };
+        self.rng.choose(&candidates).map(|&(col, _)| col)
+    }
+}
+
+/// Generates and prints puzzles.
+/// Usage: `puzzles [depth] [count] [seed]`, defaulting to 5 plies, 10 puzzles and seed 0.
+pub fn main(args: &[String]) {
+    let arg = |i: usize, default: usize| {
+        args.get(i)
+            .map(|arg| arg.parse().expect("arguments should be numbers"))
+            .unwrap_or(default)
+    };
+    let depth = arg(0, 5);
+    let count = arg(1, 10);
+    let mut generator = Generator::new(arg(2, 0) as u64, depth, true);
+
+    for _ in 0..count {
+        match generator.next_puzzle(1_000) {
+            Some(puzzle) => println!("{puzzle}\n"),
+            None => {
+                println!("// no puzzle found in 1000 games");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+
+    #[test]
+    fn diagram_round_trips() {
+        let board: BitBoard = BitBoard::read(crate::test_positions::MEDIUM_2.board);
+        assert_eq!(BitBoard::read(&diagram(&board, 4)), board);
+    }
+
+    #[test]
+    fn names_follow_test_positions() {
+        const POSITIONS: &str = include_str!("test_positions.rs");
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let name = |number| format!("pub const {difficulty}_{number}:");
+            assert!(POSITIONS.contains(&name(difficulty.taken() - 1)));
+            assert!(!POSITIONS.contains(&name(difficulty.taken())));
+        }
+
+        let puzzle = Generator::new(3, 5, true).next_puzzle(100).unwrap();
+        assert_eq!(puzzle.number, puzzle.difficulty().taken());
+        assert!(puzzle.to_string().contains(&format!(
+            "pub const {}_{}:",
+            puzzle.difficulty(),
+            puzzle.number
+        )));
+    }
+
+    #[test]
+    fn puzzles_are_exact() {
+        let mut generator = Generator::new(3, 5, true);
+        for _ in 0..3 {
+            let puzzle = generator.next_puzzle(100).unwrap();
+            assert_eq!(puzzle.depth, 5);
+            assert_eq!(puzzle.solution.len(), 5);
+
+            let board: BitBoard = BitBoard::read(&diagram(&puzzle.board, 0));
+            assert_eq!(board.curr_player(), puzzle.winner);
+            assert_eq!(
+                minimax_cached(board.clone(), 5, puzzle.winner),
+                Some(puzzle.winner)
+            );
+            assert_eq!(minimax_cached(board.clone(), 4, puzzle.winner), None);
+
+            // no other move wins, however long it takes
+            for col in column::IDXS
+                .into_iter()
+                .filter(|&col| col != puzzle.solution[0])
+            {
+                if let Some((next, _)) = board.clone_and_place(&col, &puzzle.winner) {
+                    let depth = column::COUNT * row::COUNT;
+                    assert_ne!(
+                        minimax_cached(next, depth, puzzle.winner.next()),
+                        Some(puzzle.winner)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn puzzles_are_numbered() {
+        let mut generator = Generator::new(0, 3, true);
+        let puzzles: Vec<Puzzle> = (0..6)
+            .map(|_| generator.next_puzzle(100).unwrap())
+            .collect();
+        let names: Vec<String> = puzzles
+            .iter()
+            .map(|puzzle| format!("{}_{}", puzzle.difficulty(), puzzle.number))
+            .collect();
+        for (i, name) in names.iter().enumerate() {
+            assert!(!names[..i].contains(name), "{name} is repeated");
+            assert!(
+                puzzles[i]
+                    .to_string()
+                    .contains(&format!("pub const {name}: Position"))
+            );
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::basic::*;
+use crate::board::{Board, CloneBoard};
+
+/// A small, seedable pseudo-random number generator (xorshift64*).
+/// Not cryptographically secure, but fast and reproducible.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a generator from the given seed.
+    /// Every seed (including 0) gives a valid, distinct stream.
+    pub fn seeded(seed: u64) -> Self {
+        // splitmix64 scrambles the seed, so that similar seeds give unrelated streams
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        // xorshift has a fixed point at zero
+        Rng {
+            state: if z == 0 { 0x9E37_79B9_7F4A_7C15 } else { z },
+        }
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a uniformly random number in [0, n).
+    /// Panics if `n` is zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "Tried to sample from an empty range.");
+        // multiply-shift avoids the bias of taking the modulus
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// Returns a uniformly random float in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a uniformly random element of the slice, or None if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+}
+
+/// A game, or the start of one, played from the empty board.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game<
+    B: Board<W, H, K>,
+    const W: usize = { column::COUNT },
+    const H: usize = { row::COUNT },
+    const K: usize = CONNECT,
+> {
+    /// The columns played, in order.
+    pub moves: Vec<column::Idx<W>>,
+    /// The board after the moves.
+    pub board: B,
+    /// The player who completed a line with the last move, if any.
+    pub winner: Option<Token>,
+}
+
+/// Plays uniformly random legal moves until a player wins or the board is full.
+pub fn random_game<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    rng: &mut Rng,
+) -> Game<B, W, H, K> {
+    play_random(rng, usize::MAX)
+}
+
+/// The most random games or positions a generator tries before giving up.
+pub const MAX_ATTEMPTS: usize = 10_000;
+
+/// Returns a random position after exactly `plies` moves in which nobody has won yet,
+/// retrying any game that ends sooner, or an error if none of `MAX_ATTEMPTS` games lasts.
+/// Panics if `plies` is more than the number of cells.
+pub fn random_position<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    rng: &mut Rng,
+    plies: usize,
+) -> Result<Game<B, W, H, K>, String> {
+    assert!(
+        plies <= W * H,
+        "Tried to play more moves than there are cells."
+    );
+    (0..MAX_ATTEMPTS)
+        .map(|_| play_random(rng, plies))
+        .find(|game| game.winner.is_none() && game.moves.len() == plies)
+        .ok_or_else(|| {
+            format!("no game lasted {plies} plies without a win in {MAX_ATTEMPTS} attempts")
+        })
+}
+
+/// Returns a random position as in `random_position`, that neither player can force a win
+/// from within `depth` plies, as `solver` finds, or an error if none of `MAX_ATTEMPTS` is.
+pub fn balanced_position<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K>>(
+    rng: &mut Rng,
+    plies: usize,
+    depth: usize,
+    solver: impl Fn(B, usize, Token) -> Option<Token>,
+) -> Result<Game<B, W, H, K>, String> {
+    for _ in 0..MAX_ATTEMPTS {
+        let game = random_position::<W, H, K, B>(rng, plies)?;
+        let curr = game.board.curr_player();
+        if solver(game.board.clone(), depth, curr).is_none() {
+            return Ok(game);
+        }
+    }
+    Err(format!(
+        "no position after {plies} plies was balanced to depth {depth} in {MAX_ATTEMPTS} attempts"
+    ))
+}
+
+/// Plays up to `plies` uniformly random legal moves, stopping early at a win or a full board.
+fn play_random<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    rng: &mut Rng,
+    plies: usize,
+) -> Game<B, W, H, K> {
+    let mut board = B::EMPTY;
+    let mut curr = Token::START;
+    let mut moves = Vec::new();
+
+    while moves.len() < plies {
+        let cols: Vec<column::Idx<W>> = column::Idx::<W>::ALL
+            .into_iter()
+            .filter(|col| board.can_place(col))
+            .collect();
+        let Some(&col) = rng.choose(&cols) else {
+            break;
+        };
+
+        let cell = board.place(&col, &curr).expect("the column is not full");
+        moves.push(col);
+        if board.won_at(&cell) {
+            return Game {
+                moves,
+                board,
+                winner: Some(curr),
+            };
+        }
+        curr = curr.next();
+    }
+
+    Game {
+        moves,
+        board,
+        winner: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::minimax_cached::minimax_cached;
+    use crate::board::bit_board::BitBoard;
+
+    fn replay(moves: &[column::Idx]) -> BitBoard {
+        let mut board: BitBoard = BitBoard::EMPTY;
+        let mut curr = Token::START;
+        for col in moves {
+            board.place(col, &curr).unwrap();
+            curr = curr.next();
+        }
+        board
+    }
+
+    #[test]
+    fn same_seed_same_stream() {
+        let mut a = Rng::seeded(42);
+        let mut b = Rng::seeded(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn below_stays_in_range() {
+        let mut rng = Rng::seeded(0);
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            let i = rng.below(7);
+            assert!(i < 7);
+            seen[i] = true;
+        }
+        assert!(
+            seen.iter().all(|&s| s),
+            "`Rng::below` never produced some values."
+        );
+    }
+
+    #[test]
+    fn games_are_reproducible() {
+        let a: Game<BitBoard> = random_game(&mut Rng::seeded(7));
+        let b: Game<BitBoard> = random_game(&mut Rng::seeded(7));
+        assert_eq!(a, b);
+        assert_eq!(replay(&a.moves), a.board);
+    }
+
+    #[test]
+    fn games_end_at_a_win_or_full_board() {
+        let mut rng = Rng::seeded(1);
+        for _ in 0..100 {
+            let game: Game<BitBoard> = random_game(&mut rng);
+            assert!(game.winner.is_some() || game.board.is_full());
+        }
+    }
+
+    #[test]
+    fn positions_have_no_winner() {
+        let mut rng = Rng::seeded(2);
+        for plies in [0, 10, 20, 30] {
+            let game: Game<BitBoard> = random_position(&mut rng, plies).unwrap();
+            assert_eq!(game.moves.len(), plies);
+            assert_eq!(game.winner, None);
+            assert_eq!(replay(&game.moves), game.board);
+        }
+    }
+
+    #[test]
+    fn positions_can_fill_the_board() {
+        // only a drawn game fills the board, which takes a good many attempts
+        let game: Game<BitBoard> = random_position(&mut Rng::seeded(4), 42).unwrap();
+        assert!(game.board.is_full());
+        assert_eq!(game.winner, None);
+    }
+
+    #[test]
+    fn balanced_positions_have_no_forced_win() {
+        let mut rng = Rng::seeded(3);
+        for _ in 0..10 {
+            let game: Game<BitBoard> = balanced_position(&mut rng, 16, 6, minimax_cached).unwrap();
+            let curr = game.board.curr_player();
+            assert_eq!(minimax_cached(game.board, 6, curr), None);
+        }
+    }
+}
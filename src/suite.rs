@@ -0,0 +1,277 @@
+use crate::algorithms::pruning::NodeCounter;
+use crate::basic::*;
+use crate::bench::Solver;
+use crate::board::array_board::ArrayBoard;
+use crate::board::bit_board::BitBoard;
+use crate::board::moves::Moves;
+use crate::board::symm_board::SymmBoard;
+use crate::board::{Board, CloneBoard, MutBoard};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A position from a suite file, given by a line `<moves> <score>`.
+/// The moves are the columns played from the empty board, numbered from 1.
+/// The score is from the view of the player to move: positive for a win, 22 minus the number
+/// of tokens the winner has played by the end of the game; negative for a loss; 0 for a draw.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuitePosition {
+    pub moves: Vec<column::Idx>,
+    pub score: isize,
+}
+
+impl SuitePosition {
+    /// Parses a line of a suite file.
+    pub fn parse(line: &str) -> Result<SuitePosition, String> {
+        let mut parts = line.split_whitespace();
+        let (Some(moves), Some(score), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("expected `<moves> <score>`, found `{line}`"));
+        };
+
+        let moves = moves
+            .chars()
+            .map(|ch| {
+                ch.to_digit(10)
+                    .and_then(|digit| column::Idx::try_from((digit as usize).checked_sub(1)?).ok())
+                    .ok_or(format!("invalid column `{ch}`"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let score = score
+            .parse()
+            .map_err(|_| format!("invalid score `{score}`"))?;
+        Ok(SuitePosition { moves, score })
+    }
+
+    /// Returns the player to move.
+    pub fn curr(&self) -> Token {
+        if self.moves.len().is_multiple_of(2) {
+            Token::START
+        } else {
+            Token::START.next()
+        }
+    }
+
+    /// Returns the winner under perfect play, None for a draw.
+    pub fn winner(&self) -> Option<Token> {
+        match self.score {
+            0 => None,
+            1.. => Some(self.curr()),
+            _ => Some(self.curr().next()),
+        }
+    }
+
+    /// Returns the number of plies left in the game under perfect play,
+    /// the depth needed to solve the position.
+    pub fn depth(&self) -> usize {
+        let cells = column::COUNT * row::COUNT;
+        let last = match self.winner() {
+            None => cells,
+            Some(winner) => {
+                // the winner's tokens, then the number of the winning move
+                let tokens = (cells / 2 + 1).saturating_sub(self.score.unsigned_abs());
+                if winner == Token::START {
+                    2 * tokens - 1
+                } else {
+                    2 * tokens
+                }
+            }
+        };
+        last.saturating_sub(self.moves.len())
+    }
+
+    /// Plays the moves from the empty board.
+    /// Panics if a move is into a full column.
+    pub fn board<B: Board>(&self) -> B {
+        let mut moves = Moves::EMPTY;
+        let mut curr = Token::START;
+        for col in &self.moves {
+            moves.moves.push((*col, Piece::Token(curr)));
+            curr = curr.next();
+        }
+        B::from_moves(&moves)
+    }
+}
+
+/// Parses every non-empty line of a suite file, skipping lines starting with `#`.
+pub fn parse_suite(text: &str) -> Result<Vec<SuitePosition>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| SuitePosition::parse(line).map_err(|err| format!("line {}: {err}", i + 1)))
+        .collect()
+}
+
+/// The results of solving a suite.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SuiteReport {
+    pub name: String,
+    pub positions: usize,
+    /// The number of positions solved with the expected winner.
+    pub correct: usize,
+    pub total_time: Duration,
+    pub total_nodes: u64,
+}
+
+impl SuiteReport {
+    pub fn accuracy(&self) -> f64 {
+        self.correct as f64 / self.positions.max(1) as f64
+    }
+
+    pub fn mean_time(&self) -> Duration {
+        self.total_time / self.positions.max(1) as u32
+    }
+
+    pub fn mean_nodes(&self) -> f64 {
+        self.total_nodes as f64 / self.positions.max(1) as f64
+    }
+}
+
+/// Solves every position in the suite to the depth its score needs,
+/// checking the solver finds the expected winner.
+pub fn run_suite<B: CloneBoard + MutBoard + Hash>(
+    name: &str,
+    positions: &[SuitePosition],
+    solver: Solver,
+) -> SuiteReport {
+    let mut report = SuiteReport {
+        name: name.to_owned(),
+        positions: positions.len(),
+        correct: 0,
+        total_time: Duration::ZERO,
+        total_nodes: 0,
+    };
+
+    for position in positions {
+        let board: B = position.board();
+        let counter = NodeCounter::default();
+        let start = Instant::now();
+        let result = solver.run(board, position.depth(), position.curr(), &counter);
+        report.total_time += start.elapsed();
+        report.total_nodes += counter.nodes();
+        if result == position.winner() {
+            report.correct += 1;
+        }
+    }
+    report
+}
+
+/// Runs suite files and prints a report for each.
+/// Usage: `suite [--board NAME] [--solver NAME] FILE...`,
+/// defaulting to `BitBoard` and `minimax_cached`.
+pub fn main(args: &[String]) {
+    let mut board = "BitBoard";
+    let mut solver = Solver::Cached;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--board" => board = args.next().expect("--board takes a name"),
+            "--solver" => {
+                let name = args.next().expect("--solver takes a name");
+                solver = Solver::from_name(name).expect("unknown solver");
+            }
+            file => files.push(file),
+        }
+    }
+
+    let run: fn(&str, &[SuitePosition], Solver) -> SuiteReport = match board {
+        "ArrayBoard" => run_suite::<ArrayBoard>,
+        "BitBoard" => run_suite::<BitBoard>,
+        "SymmBoard" => run_suite::<SymmBoard>,
+        _ => panic!("unknown board `{board}`"),
+    };
+
+    println!(
+        "{:<30} {:>9} {:>10} {:>15} {:>15}",
+        "suite", "positions", "accuracy", "mean time (ms)", "mean nodes"
+    );
+    for file in files {
+        let text = std::fs::read_to_string(file).expect("the suite file should be readable");
+        let positions = parse_suite(&text).unwrap_or_else(|err| panic!("{file}: {err}"));
+        let report = run(file, &positions, solver);
+        println!(
+            "{:<30} {:>9} {:>9.1}% {:>15.3} {:>15.0}",
+            report.name,
+            report.positions,
+            report.accuracy() * 100.0,
+            report.mean_time().as_secs_f64() * 1000.0,
+            report.mean_nodes()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Yellow wins with the 7th move in each, the last two by making an open three
+    const SUITE: &str = "# immediate win and double threat
+        445566 18
+        44556 -18
+        4455 18
+        ";
+
+    #[test]
+    fn parses_positions() {
+        let positions = parse_suite(SUITE).unwrap();
+        assert_eq!(positions.len(), 3);
+
+        let position = &positions[0];
+        assert_eq!(position.moves[0], column::Idx::raw(3));
+        assert_eq!(position.curr(), Token::Yellow);
+        assert_eq!(position.winner(), Some(Token::Yellow));
+        assert_eq!(position.depth(), 1);
+
+        let position = &positions[1];
+        assert_eq!(position.curr(), Token::Red);
+        assert_eq!(position.winner(), Some(Token::Yellow));
+        assert_eq!(position.depth(), 2);
+        assert_eq!(positions[2].depth(), 3);
+
+        // Red wins with their fourth token, the 8th move
+        let position = SuitePosition::parse("4545451 18").unwrap();
+        assert_eq!(position.winner(), Some(Token::Red));
+        assert_eq!(position.depth(), 1);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(SuitePosition::parse("4458 3").is_err());
+        assert!(SuitePosition::parse("4450 3").is_err());
+        assert!(SuitePosition::parse("1234567 0").is_ok());
+        assert!(SuitePosition::parse("445 x").is_err());
+        assert!(SuitePosition::parse("445").is_err());
+        assert_eq!(
+            parse_suite("44 0\n4a 1").unwrap_err(),
+            "line 2: invalid column `a`"
+        );
+    }
+
+    #[test]
+    fn solves_suite() {
+        let positions = parse_suite(SUITE).unwrap();
+        for solver in Solver::ALL {
+            let report = run_suite::<BitBoard>("test", &positions, solver);
+            assert_eq!(report.correct, 3, "{} failed the suite.", solver.name());
+            assert!(report.mean_nodes() > 0.0);
+        }
+
+        // too shallow to see the win
+        let shallow = [SuitePosition {
+            moves: positions[2].moves.clone(),
+            score: 19,
+        }];
+        assert_eq!(
+            run_suite::<BitBoard>("test", &shallow, Solver::Cached).correct,
+            0
+        );
+    }
+
+    #[test]
+    fn empty_suite_reports_zeros() {
+        let report = run_suite::<BitBoard>("empty", &[], Solver::Cached);
+        assert_eq!(report.accuracy(), 0.0);
+        assert_eq!(report.mean_time(), Duration::ZERO);
+        assert_eq!(report.mean_nodes(), 0.0);
+    }
+}
@@ -0,0 +1,630 @@
+use crate::algorithms::cross_check::{Solver, solvers, solvers_with};
+use crate::algorithms::evaluators::OpenLines;
+use crate::algorithms::negamax::{negamax, negamax_with, score_winner};
+use crate::algorithms::pruning::{DeadPosition, ForcedMoves, PruningRule};
+use crate::algorithms::win_in_n::win_in_n;
+use crate::algorithms::zugzwang::ZugzwangRule;
+use crate::basic::*;
+use crate::board::bit_board::BitBoard;
+use crate::board::{Board, CloneBoard, MutBoard};
+use hashbrown::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+
+/// A position reachable from the empty board, with its value under perfect play.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// The columns played from the empty board, from 0.
+    pub moves: Vec<usize>,
+    pub winner: Option<Token>,
+    /// The plies until the winner completes a line, or until the board is full for a draw.
+    pub plies: usize,
+}
+
+impl Entry {
+    /// Returns the player to move.
+    pub fn curr(&self) -> Token {
+        if self.moves.len().is_multiple_of(2) {
+            Token::START
+        } else {
+            Token::START.next()
+        }
+    }
+
+    /// Returns the result a search of the given depth should give:
+    /// the winner if the win is within the depth, else None.
+    pub fn result_at(&self, depth: usize) -> Option<Token> {
+        if depth >= self.plies {
+            self.winner
+        } else {
+            None
+        }
+    }
+
+    /// Plays the moves from the empty board.
+    /// Panics if a move is into a full column.
+    pub fn board<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(&self) -> B {
+        from_columns(&self.moves).expect("the moves should make a valid position")
+    }
+
+    /// Formats the entry as a line of a suite file, `<moves> <score>`, for a board with the given
+    /// number of cells. The moves are numbered from 1, or `-` for none. The score is from the view
+    /// of the player to move: for a win, half the cells rounded up, plus 1, minus the winner's
+    /// tokens at the end of the game; negated for a loss; 0 for a draw.
+    pub fn to_line(&self, cells: usize) -> String {
+        let mut moves: String = self.moves.iter().map(|col| (col + 1).to_string()).collect();
+        if moves.is_empty() {
+            moves.push('-');
+        }
+        let score = match self.winner {
+            None => 0,
+            Some(winner) => {
+                let tokens = (self.moves.len() + self.plies).div_ceil(2);
+                let score = (cells.div_ceil(2) + 1 - tokens) as isize;
+                if winner == self.curr() { score } else { -score }
+            }
+        };
+        format!("{moves} {score}")
+    }
+
+    /// Parses a line written by `to_line`.
+    pub fn parse(line: &str, cells: usize) -> Result<Entry, String> {
+        let mut parts = line.split_whitespace();
+        let (Some(moves), Some(score), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(format!("expected `<moves> <score>`, found `{line}`"));
+        };
+
+        let moves = parse_moves(moves)?;
+        let score: isize = score
+            .parse()
+            .map_err(|_| format!("invalid score `{score}`"))?;
+
+        let mut entry = Entry {
+            moves,
+            winner: None,
+            plies: 0,
+        };
+        let played = entry.moves.len();
+        if score == 0 {
+            entry.plies = cells.saturating_sub(played);
+            return Ok(entry);
+        }
+
+        let winner = if score > 0 {
+            entry.curr()
+        } else {
+            entry.curr().next()
+        };
+        // the winner's tokens, then the number of the winning move
+        let tokens = (cells.div_ceil(2) + 1)
+            .checked_sub(score.unsigned_abs())
+            .ok_or(format!("invalid score `{score}`"))?;
+        let last = if winner == Token::START {
+            2 * tokens - 1
+        } else {
+            2 * tokens
+        };
+        entry.winner = Some(winner);
+        entry.plies = last
+            .checked_sub(played)
+            .ok_or(format!("score `{score}` is for an earlier move"))?;
+        Ok(entry)
+    }
+}
+
+/// Parses columns numbered from 1, as single digits, or `-` for no moves.
+fn parse_moves(moves: &str) -> Result<Vec<usize>, String> {
+    if moves == "-" {
+        return Ok(Vec::new());
+    }
+    moves
+        .chars()
+        .map(|ch| {
+            ch.to_digit(10)
+                .and_then(|digit| (digit as usize).checked_sub(1))
+                .ok_or(format!("invalid column `{ch}`"))
+        })
+        .collect()
+}
+
+/// Plays the given columns from the empty board, alternating players from `Token::START`.
+/// Returns None if a move is off the board or into a full column.
+pub fn from_columns<const W: usize, const H: usize, const K: usize, B: Board<W, H, K>>(
+    cols: &[usize],
+) -> Option<B> {
+    let mut board = B::EMPTY;
+    let mut curr = Token::START;
+    for &col in cols {
+        board.place(&column::Idx::try_from(col).ok()?, &curr)?;
+        curr = curr.next();
+    }
+    Some(board)
+}
+
+/// The value of every position reachable from a board, the board strongly solved.
+/// Positions that have been won are not included.
+pub struct Table<B> {
+    board: B,
+    moves: Vec<usize>,
+    /// The winner and the plies to the end of the game.
+    values: HashMap<B, (Option<Token>, u8)>,
+}
+
+impl<B: Clone + Eq + Hash> Table<B> {
+    /// Strongly solves the board, reached by playing the given columns from the empty board.
+    pub fn solve<const W: usize, const H: usize, const K: usize>(board: B, moves: &[usize]) -> Self
+    where
+        B: CloneBoard<W, H, K>,
+    {
+        let mut values = HashMap::new();
+        value(board.clone(), &mut values);
+        Table {
+            board,
+            moves: moves.to_vec(),
+            values,
+        }
+    }
+
+    /// The number of positions in the table.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the winner and the plies to the end of the game, if the board is in the table.
+    pub fn get(&self, board: &B) -> Option<(Option<Token>, usize)> {
+        self.values
+            .get(board)
+            .map(|&(winner, plies)| (winner, plies as usize))
+    }
+
+    /// Calls `f` with every position, ordered by the number of moves then by the moves,
+    /// each reached by the first such moves.
+    /// Only one ply of positions is kept in memory at a time, with their moves.
+    pub fn for_each_entry<const W: usize, const H: usize, const K: usize>(
+        &self,
+        mut f: impl FnMut(Entry),
+    ) where
+        B: CloneBoard<W, H, K>,
+    {
+        let mut layer = vec![(self.board.clone(), self.moves.clone())];
+        while !layer.is_empty() {
+            let mut seen = HashMap::new();
+            let mut next_layer = Vec::new();
+            for (board, moves) in layer {
+                let (winner, plies) = self.values[&board];
+                let curr = board.curr_player();
+                for col in column::Idx::<W>::ALL {
+                    if let Some((next_board, cell)) = board.clone_and_place(&col, &curr)
+                        && !next_board.won_at(&cell)
+                        && !seen.contains_key(&next_board)
+                    {
+                        let mut next_moves = moves.clone();
+                        next_moves.push(usize::from(col));
+                        seen.insert(next_board.clone(), ());
+                        next_layer.push((next_board, next_moves));
+                    }
+                }
+                f(Entry {
+                    moves,
+                    winner,
+                    plies: plies as usize,
+                });
+            }
+            layer = next_layer;
+        }
+    }
+
+    /// Returns every position, ordered as by `for_each_entry`.
+    pub fn entries<const W: usize, const H: usize, const K: usize>(&self) -> Vec<Entry>
+    where
+        B: CloneBoard<W, H, K>,
+    {
+        let mut entries = Vec::with_capacity(self.len());
+        self.for_each_entry(|entry| entries.push(entry));
+        entries
+    }
+}
+
+/// Finds the winner and plies to the end of the game for the board and every position after it.
+fn value<const W: usize, const H: usize, const K: usize, B: CloneBoard<W, H, K> + Hash>(
+    board: B,
+    values: &mut HashMap<B, (Option<Token>, u8)>,
+) -> (Option<Token>, u8) {
+    if let Some(&value) = values.get(&board) {
+        return value;
+    }
+
+    let curr = board.curr_player();
+    let empty = column::Idx::<W>::ALL
+        .iter()
+        .filter_map(|col| board.next_cell(col))
+        .map(|cell| H - usize::from(cell.row))
+        .sum::<usize>() as u8;
+    // the fastest win, whether any move draws, and the slowest loss
+    let mut win: Option<u8> = None;
+    let mut draw = false;
+    let mut loss = 0;
+
+    for (next_board, cell) in board.next_boards(&curr) {
+        if next_board.won_at(&cell) {
+            win = Some(1);
+            continue;
+        }
+
+        match value(next_board, values) {
+            (None, _) => draw = true,
+            (Some(winner), plies) if winner == curr => {
+                win = Some(win.map_or(plies + 1, |w| w.min(plies + 1)))
+            }
+            (Some(_), plies) => loss = loss.max(plies + 1),
+        }
+    }
+
+    let value = match win {
+        Some(plies) => (Some(curr), plies),
+        None if draw || empty == 0 => (None, empty),
+        None => (Some(curr.next()), loss),
+    };
+    values.insert(board, value);
+    value
+}
+
+/// Writes the table as a suite file, with a header comment giving the board size.
+pub fn write_table<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + Hash,
+>(
+    table: &Table<B>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    assert!(W <= 9, "columns are written as single digits");
+    writeln!(out, "# {W}x{H} connect {K}, {} positions", table.len())?;
+    let mut result = Ok(());
+    table.for_each_entry(|entry| {
+        if result.is_ok() {
+            result = writeln!(out, "{}", entry.to_line(W * H));
+        }
+    });
+    result
+}
+
+/// Reads a table for a `W` by `H` board written by `write_table`,
+/// skipping blank lines and lines starting with `#`.
+pub fn read_table<const W: usize, const H: usize>(text: &str) -> Result<Vec<Entry>, String> {
+    let cells = W * H;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| Entry::parse(line, cells).map_err(|err| format!("line {}: {err}", i + 1)))
+        .collect()
+}
+
+/// How a solver's results are checked against a table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Check {
+    /// Finds every result within the depth and no further, so is searched just short of
+    /// its result, to its result and to the end of the game.
+    Exact,
+    /// As `Exact`, but only finds wins for the player to move, and None otherwise.
+    Wins,
+    /// Only searched to the end of the game, as for solvers with search extensions.
+    EndOfGame,
+}
+
+/// Negamax, with and without pruning, as a solver: it finds every win within the depth.
+pub fn negamax_solvers<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>()
+-> [(&'static str, Solver<B>); 2] {
+    [
+        ("negamax", |mut board, depth, curr| {
+            score_winner(negamax(&mut board, depth, curr, &OpenLines).0, curr)
+        }),
+        ("negamax_with", |mut board, depth, curr| {
+            let score = negamax_with(&mut board, depth, curr, &OpenLines, &DeadPosition).0;
+            score_winner(score, curr)
+        }),
+    ]
+}
+
+/// `win_in_n` as a solver, giving the player to move if they can force a win.
+pub fn win_in_n_solver<const W: usize, const H: usize, const K: usize, B: MutBoard<W, H, K>>()
+-> (&'static str, Solver<B>) {
+    ("win_in_n", |mut board, depth, curr| {
+        win_in_n(&mut board, depth, curr).map(|_| curr)
+    })
+}
+
+/// A table entry a solver got wrong.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// The solver, and the pruning rule it was given if any.
+    pub solver: String,
+    pub entry: Entry,
+    pub depth: usize,
+    pub found: Option<Token>,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let moves: Vec<String> = self.entry.moves.iter().map(|col| col.to_string()).collect();
+        write!(
+            f,
+            "{} found {:?} at depth {} after moves [{}], expected {:?}",
+            self.solver,
+            self.found,
+            self.depth,
+            moves.join(", "),
+            self.entry.result_at(self.depth)
+        )
+    }
+}
+
+/// Checks every solver against every entry, at the depths the check calls for.
+pub fn check_table<const W: usize, const H: usize, const K: usize, B: Board<W, H, K> + Clone>(
+    entries: &[Entry],
+    solvers: &[(&'static str, Solver<B>)],
+    check: Check,
+) -> Result<(), Failure> {
+    for entry in entries {
+        let board: B = entry.board();
+        let curr = entry.curr();
+        let empty = W * H - entry.moves.len();
+        let mut depths = vec![empty];
+        if check != Check::EndOfGame && entry.winner.is_some() {
+            depths.extend([entry.plies, entry.plies - 1]);
+        }
+
+        for &(solver, solve) in solvers {
+            for &depth in &depths {
+                let found = solve(board.clone(), depth, curr);
+                let expected = match check {
+                    Check::Wins => entry.result_at(depth).filter(|&winner| winner == curr),
+                    _ => entry.result_at(depth),
+                };
+                if found != expected {
+                    return Err(Failure {
+                        solver: solver.to_owned(),
+                        entry: entry.clone(),
+                        depth,
+                        found,
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Checks the solvers given the pruning rule `R` against every entry,
+/// naming the rule in any failure.
+fn check_rule<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + MutBoard<W, H, K> + Hash,
+    R: PruningRule + Default,
+>(
+    entries: &[Entry],
+    rule: &str,
+    check: Check,
+) -> Result<(), Failure> {
+    check_table(entries, &solvers_with::<W, H, K, B, R>(), check).map_err(|mut failure| {
+        failure.solver = format!("{} with {rule}", failure.solver);
+        failure
+    })
+}
+
+/// Checks every solver in `algorithms` against the table, on boards of type `B`:
+/// the minimax solvers with each pruning rule and without, negamax and `win_in_n`.
+/// Solvers that extend the search through forced moves are only checked to the end of the game.
+pub fn check_all<
+    const W: usize,
+    const H: usize,
+    const K: usize,
+    B: CloneBoard<W, H, K> + MutBoard<W, H, K> + Hash,
+>(
+    entries: &[Entry],
+) -> Result<(), Failure> {
+    check_table(entries, &solvers::<W, H, K, B>(), Check::Exact)?;
+    check_rule::<W, H, K, B, DeadPosition>(entries, "DeadPosition", Check::Exact)?;
+    check_rule::<W, H, K, B, ZugzwangRule>(entries, "ZugzwangRule", Check::Exact)?;
+    check_rule::<W, H, K, B, ForcedMoves>(entries, "ForcedMoves", Check::EndOfGame)?;
+    check_table(entries, &negamax_solvers::<W, H, K, B>(), Check::Exact)?;
+    check_table(entries, &[win_in_n_solver::<W, H, K, B>()], Check::Wins)
+}
+
+type Tool = (
+    fn(&[usize], &str) -> String,
+    fn(&str) -> Result<usize, String>,
+);
+
+/// Solves from the position after the moves and writes the table to the file,
+/// returning a summary.
+fn solve_tool<const W: usize, const H: usize, const K: usize>(
+    moves: &[usize],
+    file: &str,
+) -> String {
+    let board: BitBoard<W, H, K> =
+        from_columns(moves).expect("the moves should make a valid position");
+    let table = Table::solve(board.clone(), moves);
+
+    let file = std::fs::File::create(file).expect("the table file should be writable");
+    write_table(&table, &mut BufWriter::new(file)).expect("the table file should be writable");
+
+    let result = match table.get(&board) {
+        Some((Some(winner), plies)) => format!("won by {winner} in {plies} plies"),
+        _ => "a draw".to_owned(),
+    };
+    format!("{} positions; the first is {result}", table.len())
+}
+
+/// Checks a table file, returning the number of entries checked.
+fn check_tool<const W: usize, const H: usize, const K: usize>(text: &str) -> Result<usize, String> {
+    let entries = read_table::<W, H>(text)?;
+    check_all::<W, H, K, BitBoard<W, H, K>>(&entries).map_err(|failure| failure.to_string())?;
+    Ok(entries.len())
+}
+
+/// Returns the tools for a board size given as `WxH`, or `WxHxK` for other than connect four.
+fn tools(size: &str) -> Option<Tool> {
+    let dims = size
+        .split('x')
+        .map(str::parse)
+        .collect::<Result<Vec<usize>, _>>()
+        .ok()?;
+    let dims = match dims[..] {
+        [width, height] => (width, height, CONNECT),
+        [width, height, connect] => (width, height, connect),
+        _ => return None,
+    };
+
+    macro_rules! sizes {
+        ($(($w:literal, $h:literal, $k:literal)),*) => {
+            match dims {
+                $(
+                    ($w, $h, $k) => Some((solve_tool::<$w, $h, $k>, check_tool::<$w, $h, $k>)),
+                )*
+                _ => None,
+            }
+        };
+    }
+    sizes!(
+        (3, 3, 3),
+        (4, 3, 3),
+        (4, 4, 3),
+        (4, 4, 4),
+        (5, 4, 4),
+        (6, 4, 4),
+        (4, 5, 4),
+        (5, 5, 4),
+        (6, 5, 4),
+        (7, 6, 4)
+    )
+}
+
+/// Strongly solves a small board and writes the table, or checks the solvers against a table.
+/// Usage: `tables solve SIZE FILE [MOVES]` or `tables check SIZE FILE`, with sizes such as
+/// `4x4` or `4x3x3` for connect three. 7x6 tables must start from the position after `MOVES`,
+/// given as columns numbered from 1.
+pub fn main(args: &[String]) {
+    let [command, size, file, rest @ ..] = args else {
+        panic!("usage: tables solve SIZE FILE [MOVES] | tables check SIZE FILE");
+    };
+    let (solve, check) = tools(size).unwrap_or_else(|| panic!("unsupported size `{size}`"));
+
+    let start = Instant::now();
+    match command.as_str() {
+        "solve" => {
+            let moves = rest
+                .first()
+                .map(|moves| parse_moves(moves).expect("invalid moves"))
+                .unwrap_or_default();
+            let summary = solve(&moves, file);
+            println!("{summary} ({:.2} s)", start.elapsed().as_secs_f64());
+        }
+        "check" => {
+            let text = std::fs::read_to_string(file).expect("the table file should be readable");
+            match check(&text) {
+                Ok(count) => println!(
+                    "every solver agrees on {count} positions ({:.2} s)",
+                    start.elapsed().as_secs_f64()
+                ),
+                Err(err) => {
+                    println!("{err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => panic!("unknown command `{command}`"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::array_board::ArrayBoard;
+    use crate::random::{Game, Rng, random_position};
+
+    #[test]
+    fn solves_small_boards() {
+        let entries = Table::solve(BitBoard::<4, 4, 4>::EMPTY, &[]).entries();
+        assert_eq!(entries.len(), 139625);
+        assert_eq!(entries[0].moves, []);
+        assert_eq!(entries[0].winner, None);
+        assert_eq!(entries[0].plies, 16);
+        // the same positions and values on any board type
+        assert_eq!(
+            Table::solve(ArrayBoard::<4, 4, 4>::EMPTY, &[]).entries(),
+            entries
+        );
+
+        let entries = Table::solve(BitBoard::<4, 3, 3>::EMPTY, &[]).entries();
+        assert_eq!(entries[0].winner, Some(Token::Yellow));
+        // winning moves are always the winner's, so an odd number of plies away
+        let yellow_wins = entries[0].plies;
+        assert_eq!(yellow_wins % 2, 1);
+        assert!(entries.iter().all(|entry| {
+            entry
+                .winner
+                .is_none_or(|winner| (winner == entry.curr()) == (entry.plies % 2 == 1))
+        }));
+    }
+
+    #[test]
+    fn lines_round_trip() {
+        let table = Table::solve(BitBoard::<4, 3, 3>::EMPTY, &[]);
+        let entries = table.entries();
+        let mut text = Vec::new();
+        write_table(&table, &mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert!(text.starts_with("# 4x3 connect 3, 4659 positions\n- 2\n1 "));
+        assert_eq!(read_table::<4, 3>(&text).unwrap(), entries);
+        assert!(read_table::<4, 3>("12 9").is_err());
+    }
+
+    #[test]
+    fn solvers_match_small_tables() {
+        fn check<
+            const W: usize,
+            const H: usize,
+            const K: usize,
+            B: CloneBoard<W, H, K> + MutBoard<W, H, K> + Hash,
+        >() {
+            let entries = Table::solve(B::EMPTY, &[]).entries();
+            if let Err(failure) = check_all::<W, H, K, B>(&entries) {
+                panic!("{failure}");
+            }
+        }
+        check::<3, 3, 3, BitBoard<3, 3, 3>>();
+        check::<4, 3, 3, BitBoard<4, 3, 3>>();
+        check::<4, 3, 3, ArrayBoard<4, 3, 3>>();
+    }
+
+    #[test]
+    fn solvers_match_endgame_tables() {
+        // the last fourteen squares of a few random games
+        let mut rng = Rng::seeded(0);
+        for _ in 0..3 {
+            let game: Game<BitBoard> = random_position(&mut rng, 28).unwrap();
+            let moves: Vec<usize> = game.moves.iter().map(|&col| usize::from(col)).collect();
+            let entries = Table::solve(game.board, &moves).entries();
+            assert_eq!(entries[0].moves, moves);
+            if let Err(failure) = check_all::<7, 6, 4, BitBoard>(&entries) {
+                panic!("{failure}");
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_entries_fail() {
+        let mut entries = Table::solve(BitBoard::<4, 3, 3>::EMPTY, &[]).entries();
+        entries[0].plies += 2;
+        let failure = check_all::<4, 3, 3, BitBoard<4, 3, 3>>(&entries).unwrap_err();
+        assert_eq!(failure.solver, "minimax_mut");
+        assert_eq!(failure.entry.moves, []);
+        assert_eq!(failure.found, Some(Token::Yellow));
+    }
+}